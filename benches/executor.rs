@@ -0,0 +1,94 @@
+//! Measures task throughput of `runtime::Executor`: spawning, same-thread
+//! wakes and wakes arriving from the thread pool. Run with
+//! `cargo bench --bench executor`.
+
+use std::{
+    cell::Cell,
+    rc::Rc,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+#[allow(dead_code)]
+#[path = "../src/runtime/mod.rs"]
+mod runtime;
+
+const TARGET: Duration = Duration::from_secs(2);
+
+/// Yields back to the executor once, waking itself from the same thread.
+fn yield_now() -> impl Future<Output = ()> {
+    let mut yielded = false;
+
+    std::future::poll_fn(move |cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+}
+
+/// Runs `round` until the target time has passed, returning the time per
+/// operation given `ops` operations per round.
+fn measure(ops: usize, round: impl Fn(&runtime::Executor)) -> Duration {
+    let executor = runtime::Executor::default();
+    let mut rounds = 0;
+    let start = Instant::now();
+
+    while start.elapsed() < TARGET {
+        round(&executor);
+        rounds += 1;
+    }
+
+    start.elapsed() / (rounds * ops) as u32
+}
+
+fn main() {
+    const TASKS: usize = 10_000;
+    const YIELDS: usize = 100;
+
+    let spawn = measure(TASKS, |executor| {
+        let spawner = executor.spawner();
+        let done = Rc::new(Cell::new(0));
+
+        for _ in 0..TASKS {
+            let done = done.clone();
+            spawner.defer(async move { done.set(done.get() + 1) });
+        }
+
+        executor.run();
+        assert_eq!(done.get(), TASKS);
+    });
+
+    let local = measure(TASKS * YIELDS, |executor| {
+        let spawner = executor.spawner();
+
+        for _ in 0..TASKS {
+            spawner.spawn(async {
+                for _ in 0..YIELDS {
+                    yield_now().await;
+                }
+            });
+        }
+
+        executor.run();
+    });
+
+    let remote = measure(TASKS, |executor| {
+        let spawner = executor.spawner();
+
+        for _ in 0..TASKS {
+            spawner.spawn(async {
+                runtime::util::unblock(|| ()).await;
+            });
+        }
+
+        executor.run();
+    });
+
+    println!("spawn        {spawn:>10.2?} per task");
+    println!("local wake   {local:>10.2?} per wake");
+    println!("remote wake  {remote:>10.2?} per wake");
+}
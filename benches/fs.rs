@@ -0,0 +1,138 @@
+//! Compares `runtime::fs` reads and writes on the thread pool against
+//! io_uring. Run with `cargo bench --bench fs --features io-uring`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::Thread,
+    time::{Duration, Instant},
+};
+
+#[allow(dead_code)]
+#[path = "../src/runtime/mod.rs"]
+mod runtime;
+
+const SIZES: [usize; 3] = [4 * 1024, 256 * 1024, 16 * 1024 * 1024];
+const FILES: usize = 64;
+const TARGET: Duration = Duration::from_secs(2);
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let cx = &mut Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return output;
+        }
+
+        std::thread::park();
+    }
+}
+
+/// Runs `op` over every file concurrently, returning the time per file.
+fn measure<F: Future<Output = std::io::Result<()>>>(
+    files: &[PathBuf],
+    op: impl Fn(PathBuf) -> F,
+) -> Duration {
+    let mut rounds = 0;
+    let start = Instant::now();
+
+    while start.elapsed() < TARGET {
+        let mut futures: Vec<_> = files
+            .iter()
+            .map(|f| Some(Box::pin(op(f.clone()))))
+            .collect();
+
+        block_on(std::future::poll_fn(|cx| {
+            for slot in futures.iter_mut() {
+                if let Some(Poll::Ready(result)) = slot.as_mut().map(|f| f.as_mut().poll(cx)) {
+                    result.unwrap();
+                    *slot = None;
+                }
+            }
+
+            match futures.iter().all(Option::is_none) {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            }
+        }));
+
+        rounds += 1;
+    }
+
+    start.elapsed() / (rounds * files.len()) as u32
+}
+
+fn report(name: &str, size: usize, thread_pool: Duration, uring: Option<Duration>) {
+    print!("{name:<6} {:>9}B  thread pool {thread_pool:>10.2?}", size);
+
+    match uring {
+        Some(uring) => println!(
+            "  io_uring {uring:>10.2?}  ({:+.1}%)",
+            (uring.as_secs_f64() / thread_pool.as_secs_f64() - 1.0) * 100.0
+        ),
+        None => println!(),
+    }
+}
+
+fn bench(dir: &Path, size: usize) {
+    let contents = vec![0x5a; size];
+    let files: Vec<_> = (0..FILES)
+        .map(|i| dir.join(format!("{size}-{i}")))
+        .collect();
+
+    let pool_write = measure(&files, |path| {
+        let contents = contents.clone();
+        async move { runtime::util::unblock(move || std::fs::write(path, contents)).await }
+    });
+    let pool_read = measure(&files, |path| async move {
+        runtime::util::unblock(move || std::fs::read(path).map(drop)).await
+    });
+
+    #[cfg(feature = "io-uring")]
+    let (uring_write, uring_read) = match runtime::uring::supported() {
+        true => (
+            Some(measure(&files, |path| {
+                let contents = contents.clone();
+                async move { runtime::uring::write(&path, contents).await }
+            })),
+            Some(measure(&files, |path| async move {
+                runtime::uring::read(&path).await.map(drop)
+            })),
+        ),
+
+        false => (None, None),
+    };
+
+    #[cfg(not(feature = "io-uring"))]
+    let (uring_write, uring_read) = (None, None);
+
+    report("write", size, pool_write, uring_write);
+    report("read", size, pool_read, uring_read);
+}
+
+fn main() {
+    // starts the reactor thread
+    let _executor = runtime::Executor::default();
+
+    let dir = tempfile::tempdir().unwrap();
+
+    #[cfg(feature = "io-uring")]
+    if !runtime::uring::supported() {
+        println!("io_uring is unavailable, only measuring the thread pool");
+    }
+
+    for size in SIZES {
+        bench(dir.path(), size);
+    }
+}
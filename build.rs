@@ -16,6 +16,7 @@ fn main() {
     println!("cargo:rustc-link-lib=static=Luau.RequireNavigator");
     println!("cargo:rustc-link-lib=static=Luau.Config");
     println!("cargo:rustc-link-lib=static=Luau.Compiler");
+    println!("cargo:rustc-link-lib=static=Luau.CodeGen");
     println!("cargo:rustc-link-lib=static=Luau.Ast");
     println!("cargo:rustc-link-lib=stdc++");
 }
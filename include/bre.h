@@ -0,0 +1,117 @@
+/*
+ * Native plugin interface for bre.
+ *
+ * A plugin is a shared library exporting `bre_plugin_open`. It is loaded by
+ * `require("@plugin/name")`, which looks for the library registered under
+ * `[plugins]` in bre.toml, then for `libname.so` or `name.so` in `plugins/`.
+ *
+ * `bre_plugin_open` pushes the module value, usually a table of functions,
+ * and returns 1. Only the functions in `bre_api` may be used, and `error`
+ * unwinds through the caller, so C plugins must be built with -fexceptions.
+ */
+
+#ifndef BRE_H
+#define BRE_H
+
+#include <stddef.h>
+
+#define BRE_ABI_VERSION 1
+
+/* Pass as `nresults` to keep every result of a call. */
+#define BRE_MULTRET (-1)
+
+/* The index of a closure's `i`th upvalue, counting from 1. */
+#define BRE_UPVALUEINDEX(i) (-10002 - (i))
+
+typedef struct lua_State lua_State;
+typedef int (*bre_function)(lua_State *L);
+typedef void (*bre_dtor)(void *data);
+
+enum bre_type {
+    BRE_TNONE = -1,
+    BRE_TNIL = 0,
+    BRE_TBOOLEAN,
+    BRE_TLIGHTUSERDATA,
+    BRE_TNUMBER,
+    BRE_TVECTOR,
+    BRE_TSTRING,
+    BRE_TTABLE,
+    BRE_TFUNCTION,
+    BRE_TUSERDATA,
+    BRE_TTHREAD,
+    BRE_TBUFFER,
+};
+
+/* Functions are only ever appended, check `version` before using newer ones. */
+typedef struct bre_api {
+    unsigned int version;
+
+    int (*get_top)(lua_State *L);
+    void (*set_top)(lua_State *L, int idx);
+    void (*push_copy)(lua_State *L, int idx);
+    void (*remove)(lua_State *L, int idx);
+    /* Moves the top value to `idx`, shifting the ones above it up. */
+    void (*insert)(lua_State *L, int idx);
+    int (*type_of)(lua_State *L, int idx);
+    int (*raw_equal)(lua_State *L, int idx1, int idx2);
+
+    void (*push_nil)(lua_State *L);
+    void (*push_boolean)(lua_State *L, int b);
+    void (*push_number)(lua_State *L, double n);
+    void (*push_string)(lua_State *L, const char *s, size_t len);
+    void (*push_table)(lua_State *L);
+    void (*push_function)(lua_State *L, const char *name, bre_function f);
+    /* Pops `nup` values into the upvalues of a new closure. */
+    void (*push_closure)(lua_State *L, const char *name, bre_function f, int nup);
+    void (*push_light_userdata)(lua_State *L, void *p);
+    /* Pushes an uninitialized userdata of `size` bytes. `dtor`, if not NULL,
+     * runs on its data before it's freed. */
+    void *(*push_userdata)(lua_State *L, size_t size, bre_dtor dtor);
+
+    /*
+     * Table access is raw, skipping metamethods, and `idx` must be a table.
+     * The getters push the value and return its type.
+     */
+
+    int (*get_field)(lua_State *L, int idx, const char *key);
+    /* Pops a value and stores it in the table at `idx` under `key`. */
+    void (*set_field)(lua_State *L, int idx, const char *key);
+    /* Pops a key and pushes its value. */
+    int (*get_table)(lua_State *L, int idx);
+    /* Pops a value and a key below it, and stores the value under the key. */
+    void (*set_table)(lua_State *L, int idx);
+    int (*get_index)(lua_State *L, int idx, int n);
+    void (*set_index)(lua_State *L, int idx, int n);
+    /* Pops a key, nil to start, and pushes the next key and value, or
+     * returns 0 with nothing pushed once the table is done. */
+    int (*next)(lua_State *L, int idx);
+    size_t (*len)(lua_State *L, int idx);
+    /* Pushes the metatable of the value at `idx`, or returns 0 if it has
+     * none. */
+    int (*push_metatable)(lua_State *L, int idx);
+    /* Pops a table, or nil, and sets it as the metatable at `idx`. */
+    void (*set_metatable)(lua_State *L, int idx);
+
+    int (*to_boolean)(lua_State *L, int idx);
+    double (*to_number)(lua_State *L, int idx, int *isnum);
+    const char *(*to_string)(lua_State *L, int idx, size_t *len);
+    /* The data of a userdata or light userdata, or NULL. */
+    void *(*to_userdata)(lua_State *L, int idx);
+
+    /* Keeps the value at `idx` alive until `unref`, for holding on to
+     * callbacks and metatables between calls. */
+    int (*new_ref)(lua_State *L, int idx);
+    void (*push_ref)(lua_State *L, int ref);
+    void (*unref)(lua_State *L, int ref);
+
+    /* Pops a function and its `nargs` arguments and pushes `nresults`
+     * results. `call` unwinds on errors, `pcall` returns non-zero with the
+     * error pushed instead. Neither may yield. */
+    void (*call)(lua_State *L, int nargs, int nresults);
+    int (*pcall)(lua_State *L, int nargs, int nresults);
+    void (*error)(lua_State *L, const char *msg, size_t len);
+} bre_api;
+
+int bre_plugin_open(const bre_api *api, lua_State *L);
+
+#endif
@@ -0,0 +1,321 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{globals::require, luau, runtime};
+
+const WARMUP: Duration = Duration::from_millis(200);
+const SAMPLE_TARGET: Duration = Duration::from_millis(10);
+const MIN_SAMPLES: usize = 10;
+const MAX_SAMPLES: usize = 500;
+const MAX_TIME: Duration = Duration::from_secs(5);
+const TARGET_ERROR: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+    pub samples: usize,
+}
+
+impl Stats {
+    fn new(samples: &mut [f64]) -> Self {
+        samples.sort_by(f64::total_cmp);
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+
+        let mid = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[mid - 1] + samples[mid]) / 2.0
+        } else {
+            samples[mid]
+        };
+
+        Self {
+            mean,
+            median,
+            stddev: variance.sqrt(),
+            samples: samples.len(),
+        }
+    }
+
+    fn relative_error(&self) -> f64 {
+        self.stddev / (self.samples as f64).sqrt() / self.mean
+    }
+}
+
+pub type Baseline = BTreeMap<String, Stats>;
+
+#[derive(Clone, Copy)]
+pub struct Mode {
+    pub opt_level: luau::OptLevel,
+    pub codegen: bool,
+}
+
+impl Mode {
+    pub fn all() -> Vec<Mode> {
+        let mut modes = Vec::new();
+
+        for level in 0..=2 {
+            let opt_level = level.try_into().unwrap();
+
+            modes.push(Mode {
+                opt_level,
+                codegen: false,
+            });
+
+            if luau::codegen_supported() {
+                modes.push(Mode {
+                    opt_level,
+                    codegen: true,
+                });
+            }
+        }
+
+        modes
+    }
+
+    fn compiler(&self) -> luau::Compiler {
+        luau::Compiler::default()
+            .with_opt_level(self.opt_level)
+            .with_codegen(self.codegen)
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "O{}", self.opt_level as u8)?;
+
+        if self.codegen {
+            write!(f, "+native")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn discover(root: &Path) -> Vec<PathBuf> {
+    fn visit(dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if path.is_dir() {
+                visit(&path, out);
+            } else if name.ends_with(".bench.luau") {
+                out.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+
+    if root.is_file() {
+        files.push(root.to_owned());
+    } else {
+        visit(root, &mut files);
+    }
+
+    files.sort();
+    files
+}
+
+/// Calls a benchmark `n` times from Luau, so it can yield to the scheduler.
+const DRIVER: &[u8] = b"local f, n = ...\nfor _ = 1, n do\n\tf()\nend\n";
+
+struct Runner<'a> {
+    main: luau::Main,
+    executor: &'a runtime::Executor,
+    driver: luau::Bytecode,
+}
+
+impl Runner<'_> {
+    /// Times `iters` calls to `func`, until every thread they started has
+    /// finished.
+    fn call(&self, func: &luau::Ref, iters: u64) -> Result<Duration, String> {
+        let (_r, thread) = self.main.new_thread();
+        let stack = thread.stack();
+
+        stack.push_bytecode(c"bench", &self.driver);
+        stack.push_ref(func);
+        stack.push_number(iters as f64);
+
+        let start = Instant::now();
+        self.main.spawn(&thread, 2);
+        self.executor.run();
+        let elapsed = start.elapsed();
+
+        match thread.status() {
+            luau::Status::Ok => Ok(elapsed),
+            luau::Status::Yield => Err("benchmark never finished".to_owned()),
+            _ => Err(self.main.error_message(&thread)),
+        }
+    }
+}
+
+fn measure(runner: &Runner, func: &luau::Ref) -> Result<Stats, String> {
+    let mut iters = 1;
+    let warmup = Instant::now();
+
+    loop {
+        let elapsed = runner.call(func, iters)?;
+
+        if elapsed >= SAMPLE_TARGET && warmup.elapsed() >= WARMUP {
+            break;
+        }
+
+        if elapsed < SAMPLE_TARGET {
+            iters *= 2;
+        }
+    }
+
+    let mut samples = Vec::new();
+    let start = Instant::now();
+
+    loop {
+        let elapsed = runner.call(func, iters)?;
+        samples.push(elapsed.as_secs_f64() / iters as f64);
+
+        if samples.len() >= MAX_SAMPLES
+            || (samples.len() >= MIN_SAMPLES && start.elapsed() >= MAX_TIME)
+        {
+            break;
+        }
+
+        if samples.len() >= MIN_SAMPLES
+            && Stats::new(&mut samples.clone()).relative_error() < TARGET_ERROR
+        {
+            break;
+        }
+    }
+
+    Ok(Stats::new(&mut samples))
+}
+
+pub fn run(
+    executor: &runtime::Executor,
+    path: &Path,
+    mode: Mode,
+    require: &require::Options,
+    filter: Option<&str>,
+    mut report: impl FnMut(&str, Result<Stats, String>),
+) -> Result<(), String> {
+    let compiler = mode.compiler();
+    let luau = luau::Luau::new(executor.spawner(), compiler.clone(), require.clone());
+    let main = luau.main();
+
+    let source =
+        std::fs::read(path).map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+    let bytecode = compiler.compile(&source);
+
+    if let Some(err) = bytecode.error() {
+        return Err(err.to_owned());
+    }
+
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    let (status, module) = main.execute(&path, &bytecode);
+    let thread = module.to_thread();
+    let stack = thread.stack();
+
+    match status {
+        luau::Status::Ok => {}
+        luau::Status::Yield => return Err("benchmark module yielded while loading".to_owned()),
+        _ => {
+            return Err(format!(
+                "benchmark module errored while loading: {}",
+                main.error_message(&thread)
+            ));
+        }
+    }
+
+    if !stack.is_table(-1) {
+        return Err("benchmark module must return a table of functions".to_owned());
+    }
+
+    let mut benches = Vec::new();
+
+    stack.push_nil();
+    while stack.next(-2) {
+        if let (Some(name), true) = (stack.to_string_str(-2), stack.is_function(-1)) {
+            benches.push((name.to_owned(), stack.to_ref(-1)));
+        }
+
+        stack.pop(1);
+    }
+
+    benches.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let runner = Runner {
+        main,
+        executor,
+        driver: compiler.compile(DRIVER),
+    };
+
+    for (name, func) in &benches {
+        if filter.is_some_and(|f| !name.contains(f)) {
+            continue;
+        }
+
+        report(name, measure(&runner, func));
+    }
+
+    Ok(())
+}
+
+pub fn key(path: &Path, name: &str, mode: Mode) -> String {
+    format!("{}::{name}@{mode}", path.display())
+}
+
+pub fn format_time(secs: f64) -> String {
+    match secs {
+        s if s < 1e-6 => format!("{:.2}ns", s * 1e9),
+        s if s < 1e-3 => format!("{:.2}µs", s * 1e6),
+        s if s < 1.0 => format!("{:.2}ms", s * 1e3),
+        s => format!("{s:.2}s"),
+    }
+}
+
+/// Compares a result against its baseline, returning the relative change in
+/// the mean and whether the change is outside the measurement noise, or
+/// `None` if the baseline mean is zero and there's nothing to compare to.
+pub fn compare(current: &Stats, baseline: &Stats) -> Option<(f64, bool)> {
+    if baseline.mean <= 0.0 {
+        return None;
+    }
+
+    let change = (current.mean - baseline.mean) / baseline.mean;
+
+    let noise = (current.stddev.powi(2) / current.samples as f64
+        + baseline.stddev.powi(2) / baseline.samples as f64)
+        .sqrt();
+
+    Some((change, (current.mean - baseline.mean).abs() > 2.0 * noise))
+}
+
+pub fn load_baseline(path: &Path) -> Result<Baseline, String> {
+    let contents =
+        std::fs::read(path).map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+    serde_json::from_slice(&contents)
+        .map_err(|e| format!("failed to parse '{}': {e}", path.display()))
+}
+
+pub fn save_baseline(path: &Path, baseline: &Baseline) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(baseline).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| format!("failed to write '{}': {e}", path.display()))
+}
@@ -1,8 +1,12 @@
-use std::{fs::read, path::PathBuf};
+use std::{
+    ffi::OsString,
+    fs::read,
+    path::{Path, PathBuf},
+};
 
 use clap::{Parser, Subcommand};
 
-use crate::{luau, runtime};
+use crate::{bench, globals::require, libs, luau, pkg, runtime, transform};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -19,6 +23,91 @@ enum Commands {
         /// The optimization level to compile with.
         #[arg(short, default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=2))]
         opt_level: u8,
+
+        /// Serve bre implementations of Lune's libraries under `@lune`.
+        #[arg(long)]
+        lune: bool,
+
+        /// Arguments for the script, available as `process.args` with `--lune`.
+        #[arg(last = true)]
+        args: Vec<OsString>,
+    },
+
+    /// List the Lune APIs `run --lune` does not support yet.
+    Compat,
+
+    /// Apply source-to-source passes to a file.
+    Transform {
+        /// The file to transform.
+        input: PathBuf,
+
+        /// Where to write the result, defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Remove comments and unnecessary whitespace.
+        #[arg(long)]
+        minify: bool,
+
+        /// Remove `if NAME then ... end` blocks guarded by this name.
+        #[arg(long, value_name = "NAME")]
+        strip: Vec<String>,
+
+        /// Remove type annotations and type declarations.
+        #[arg(long)]
+        strip_types: bool,
+
+        /// Inline a constant, given as NAME or NAME=VALUE.
+        #[arg(short = 'D', value_name = "NAME[=VALUE]")]
+        define: Vec<String>,
+
+        /// Rewrite relative require paths to .luaurc aliases.
+        #[arg(long)]
+        aliases: bool,
+    },
+
+    /// Run the benchmarks in `*.bench.luau` files.
+    Bench {
+        /// The file or directory to search for benchmarks.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Only run benchmarks whose name contains this string.
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// A baseline file to compare results against.
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Write the results to a baseline file.
+        #[arg(long)]
+        save_baseline: Option<PathBuf>,
+
+        /// The slowdown, in percent, that counts as a regression.
+        #[arg(long, default_value_t = 5.0)]
+        threshold: f64,
+    },
+
+    /// Add a dependency to the project and install it.
+    Add {
+        /// A local directory, git repository or tarball.
+        source: String,
+
+        /// The version range to accept, defaults to `^` the current version.
+        #[arg(long)]
+        version: Option<semver::VersionReq>,
+
+        /// The git revision to check out.
+        #[arg(long)]
+        rev: Option<String>,
+    },
+
+    /// Install the dependencies of the project in the current directory.
+    Install {
+        /// Ignore the lockfile and resolve every dependency again.
+        #[arg(long)]
+        update: bool,
     },
 }
 
@@ -26,10 +115,20 @@ pub fn cli() {
     let args = Args::parse();
 
     match args.command {
-        Commands::Run { opt_level } => {
+        Commands::Run {
+            opt_level,
+            lune,
+            args,
+        } => {
             let executor = runtime::Executor::default();
             let compiler = luau::Compiler::default().with_opt_level(opt_level.try_into().unwrap());
-            let luau = luau::Luau::new(executor.spawner(), compiler.clone());
+
+            let mut require = require_options();
+            if lune {
+                require = require.with_provider("lune", libs::lune::Lune { args });
+            }
+
+            let luau = luau::Luau::new(executor.spawner(), compiler.clone(), require);
 
             let path = PathBuf::from("./main.luau").canonicalize().unwrap();
             let code = std::fs::read(&path).unwrap();
@@ -39,5 +138,160 @@ pub fn cli() {
 
             executor.run();
         }
+
+        Commands::Transform {
+            input,
+            output,
+            minify,
+            strip,
+            strip_types,
+            define,
+            aliases,
+        } => {
+            let transform = define
+                .iter()
+                .try_fold(transform::Transform::default(), |t, d| t.with_define(d))
+                .unwrap_or_else(|e| fail(e));
+
+            let transform = transform
+                .with_minify(minify)
+                .with_strip(strip)
+                .with_strip_types(strip_types)
+                .with_aliases(aliases);
+
+            let source = std::fs::read_to_string(&input)
+                .unwrap_or_else(|e| fail(format!("failed to read '{}': {e}", input.display())));
+
+            let result = transform.apply(&input, &source).unwrap_or_else(|e| fail(e));
+
+            if let Some(err) = luau::Compiler::default().compile(result.as_bytes()).error() {
+                fail(format!("transformed output does not compile: {err}"));
+            }
+
+            match output {
+                Some(output) => std::fs::write(&output, result).unwrap_or_else(|e| {
+                    fail(format!("failed to write '{}': {e}", output.display()))
+                }),
+
+                None => print!("{result}"),
+            }
+        }
+
+        Commands::Compat => libs::lune::report(),
+
+        Commands::Bench {
+            path,
+            filter,
+            baseline,
+            save_baseline,
+            threshold,
+        } => {
+            let executor = runtime::Executor::default();
+            let require = require_options();
+            let previous = baseline.map(|p| bench::load_baseline(&p).unwrap_or_else(|e| fail(e)));
+
+            let files = bench::discover(&path);
+            if files.is_empty() {
+                fail(format!("no benchmarks found in '{}'", path.display()));
+            }
+
+            let mut results = bench::Baseline::new();
+            let mut regressions = 0;
+            let mut failures = 0;
+
+            for file in files {
+                println!("{}", file.display());
+
+                for mode in bench::Mode::all() {
+                    let result = bench::run(
+                        &executor,
+                        &file,
+                        mode,
+                        &require,
+                        filter.as_deref(),
+                        |name, stats| {
+                            let stats = match stats {
+                                Ok(stats) => stats,
+                                Err(e) => {
+                                    println!("  {name} [{mode}] errored: {e}");
+                                    failures += 1;
+                                    return;
+                                }
+                            };
+
+                            let key = bench::key(&file, name, mode);
+                            let mut line = format!(
+                                "  {name} [{mode}] {} ± {} (median {}, {} samples)",
+                                bench::format_time(stats.mean),
+                                bench::format_time(stats.stddev),
+                                bench::format_time(stats.median),
+                                stats.samples,
+                            );
+
+                            if let Some(base) = previous.as_ref().and_then(|b| b.get(&key)) {
+                                match bench::compare(&stats, base) {
+                                    Some((change, significant)) => {
+                                        line.push_str(&format!(" {:+.1}%", change * 100.0));
+
+                                        if significant && change * 100.0 > threshold {
+                                            line.push_str(" regression");
+                                            regressions += 1;
+                                        }
+                                    }
+
+                                    None => line.push_str(" n/a"),
+                                }
+                            }
+
+                            println!("{line}");
+                            results.insert(key, stats);
+                        },
+                    );
+
+                    if let Err(e) = result {
+                        println!("  [{mode}] failed: {e}");
+                        failures += 1;
+                    }
+                }
+            }
+
+            if let Some(path) = save_baseline {
+                bench::save_baseline(&path, &results).unwrap_or_else(|e| fail(e));
+            }
+
+            if regressions > 0 || failures > 0 {
+                fail(format!(
+                    "{regressions} regression(s), {failures} failure(s)"
+                ));
+            }
+        }
+
+        Commands::Add {
+            source,
+            version,
+            rev,
+        } => pkg::add(Path::new("."), &source, version, rev).unwrap_or_else(|e| fail(e)),
+
+        Commands::Install { update } => {
+            pkg::install(Path::new("."), update).unwrap_or_else(|e| fail(e))
+        }
     }
 }
+
+/// The require search options for the project in the current directory.
+fn require_options() -> require::Options {
+    let manifest: pkg::manifest::Manifest =
+        pkg::manifest::read(Path::new(pkg::manifest::MANIFEST)).unwrap_or_else(|e| fail(e));
+
+    require::Options::default()
+        .with_env()
+        .with_roots(manifest.require.paths)
+        .with_lua(manifest.require.lua)
+        .with_plugins(manifest.plugins)
+        .with_plugin_dirs([PathBuf::from("plugins")])
+}
+
+fn fail(msg: impl std::fmt::Display) -> ! {
+    eprintln!("{msg}");
+    std::process::exit(1)
+}
@@ -0,0 +1,212 @@
+//! `coroutine.resume`, `coroutine.wrap` and `coroutine.close`, aware of async
+//! builtins. When a coroutine yields to wait on one, whoever resumed it
+//! yields too, up to the thread the scheduler runs, and only continues once
+//! the coroutine yields or returns for real. Generators can call `fs.read` or
+//! `task.wait` without their callers noticing.
+
+use crate::{globals, luau};
+
+/// How resuming a coroutine ended, with its values or error on top of the
+/// resumer's stack.
+enum Outcome {
+    Values(u32),
+    Error,
+
+    /// The coroutine is waiting on an async builtin, and the resumer has to
+    /// yield until it's done.
+    Suspended,
+}
+
+/// Resumes `co` with the `nargs` values on top of the stack.
+fn start(ctx: &luau::Context, co: &luau::Thread, nargs: u32) -> Outcome {
+    let status = match co.coro_status() {
+        _ if ctx.main().is_suspended(co) => Some("waiting"),
+        luau::CoroStatus::Suspended => None,
+        luau::CoroStatus::Run => Some("running"),
+        luau::CoroStatus::Normal => Some("normal"),
+        luau::CoroStatus::Finished | luau::CoroStatus::Errored => Some("dead"),
+    };
+
+    if let Some(status) = status {
+        ctx.pop(nargs);
+        ctx.push_string(format!("cannot resume {status} coroutine"));
+        return Outcome::Error;
+    }
+
+    ctx.xmove(co, nargs);
+    let status = co.resume(Some(&ctx.thread()), nargs);
+
+    collect(ctx, co, status)
+}
+
+/// Moves what `co` yielded or returned over to the resumer, unless it's
+/// waiting on an async builtin.
+fn collect(ctx: &luau::Context, co: &luau::Thread, status: luau::Status) -> Outcome {
+    let main = ctx.main();
+    let stack = co.stack();
+
+    match status {
+        luau::Status::Yield if main.is_suspended(co) => {
+            main.await_coroutine(&ctx.thread(), co);
+            Outcome::Suspended
+        }
+
+        luau::Status::Ok | luau::Status::Yield => {
+            let n = stack.get_top();
+
+            ctx.check(n + 1);
+            stack.xmove(&ctx.thread(), n);
+
+            Outcome::Values(n)
+        }
+
+        _ => {
+            stack.xmove(&ctx.thread(), 1);
+            Outcome::Error
+        }
+    }
+}
+
+pub struct Coroutine;
+
+impl Coroutine {
+    extern "C-unwind" fn resume(ctx: luau::Context) -> luau::FnReturn {
+        let co = ctx.arg_thread(1);
+        let outcome = start(&ctx, &co, ctx.get_top() - 1);
+
+        Self::resume_finish(ctx, outcome)
+    }
+
+    /// Continues `resume` once a suspended coroutine is done waiting. The
+    /// scheduler passes the coroutine back as the only argument.
+    extern "C-unwind" fn resume_cont(ctx: luau::Context, _: luau::Status) -> luau::FnReturn {
+        let co = ctx.to_thread(1).unwrap();
+        let outcome = collect(&ctx, &co, co.status());
+
+        Self::resume_finish(ctx, outcome)
+    }
+
+    fn resume_finish(ctx: luau::Context, outcome: Outcome) -> luau::FnReturn {
+        match outcome {
+            Outcome::Values(n) => {
+                ctx.push_boolean(true);
+                ctx.insert(-(n as i32) - 1);
+                ctx.ret_with(n + 1)
+            }
+
+            Outcome::Error => {
+                ctx.push_boolean(false);
+                ctx.insert(-2);
+                ctx.ret_with(2)
+            }
+
+            Outcome::Suspended => ctx.yld(),
+        }
+    }
+
+    extern "C-unwind" fn wrap(ctx: luau::Context) -> luau::FnReturn {
+        if !ctx.is_function(1) {
+            ctx.push_error("bad argument #1 to function (function expected)");
+        }
+
+        let co = ctx.push_thread_new();
+        ctx.xpush(&co, 1);
+
+        ctx.push_closure_cont(c"Coroutine::wrapped", Self::wrapped, Self::wrapped_cont, 1);
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn wrapped(ctx: luau::Context) -> luau::FnReturn {
+        let co = ctx.to_thread(luau::upvalue_idx(1)).unwrap();
+        let outcome = start(&ctx, &co, ctx.get_top());
+
+        Self::wrapped_finish(ctx, outcome)
+    }
+
+    extern "C-unwind" fn wrapped_cont(ctx: luau::Context, _: luau::Status) -> luau::FnReturn {
+        let co = ctx.to_thread(luau::upvalue_idx(1)).unwrap();
+        let outcome = collect(&ctx, &co, co.status());
+
+        Self::wrapped_finish(ctx, outcome)
+    }
+
+    fn wrapped_finish(ctx: luau::Context, outcome: Outcome) -> luau::FnReturn {
+        match outcome {
+            Outcome::Values(n) => ctx.ret_with(n),
+
+            Outcome::Error => {
+                // like Luau, point string errors at the caller
+                if ctx.is_string(-1) {
+                    ctx.push_where(1);
+                    ctx.insert(-2);
+                    ctx.concat(2);
+                }
+
+                ctx.error()
+            }
+
+            Outcome::Suspended => ctx.yld(),
+        }
+    }
+
+    /// Closes the coroutine like `task.cancel`, dropping whatever it was
+    /// waiting on, and returns false with its error if it had failed.
+    extern "C-unwind" fn close(ctx: luau::Context) -> luau::FnReturn {
+        let co = ctx.arg_thread(1);
+
+        let status = match co.coro_status() {
+            luau::CoroStatus::Run => Some("running"),
+            luau::CoroStatus::Normal => Some("normal"),
+            _ => None,
+        };
+
+        if let Some(status) = status {
+            ctx.push_error(format!("cannot close {status} coroutine"));
+        }
+
+        let n = match co.status() {
+            luau::Status::Ok | luau::Status::Yield => {
+                ctx.push_boolean(true);
+                1
+            }
+
+            _ => {
+                ctx.push_boolean(false);
+
+                match co.stack().get_top() {
+                    0 => 1,
+                    _ => {
+                        co.stack().xmove(&ctx.thread(), 1);
+                        2
+                    }
+                }
+            }
+        };
+
+        ctx.main().cancel(&co);
+        globals::require::cancel(&ctx, &co);
+        co.reset();
+
+        ctx.ret_with(n)
+    }
+}
+
+/// Swaps the coroutine library's `resume`, `wrap` and `close` for ours.
+pub fn open(stack: &luau::Stack) {
+    stack.table_get_raw_field(luau::GLOBALS_IDX, c"coroutine"); // coroutine
+
+    stack.push_function_cont(
+        c"Coroutine::resume",
+        Coroutine::resume,
+        Coroutine::resume_cont,
+    );
+    stack.table_set_raw_field(-2, c"resume");
+
+    stack.push_function(c"Coroutine::wrap", Coroutine::wrap);
+    stack.table_set_raw_field(-2, c"wrap");
+
+    stack.push_function(c"Coroutine::close", Coroutine::close);
+    stack.table_set_raw_field(-2, c"close");
+
+    stack.pop(1);
+}
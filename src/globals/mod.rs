@@ -1,2 +1,3 @@
+pub mod coroutine;
 pub mod require;
 pub mod task;
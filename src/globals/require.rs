@@ -1,10 +1,16 @@
 use std::{
-    ffi::{CStr, c_char, c_int, c_void},
-    io::Read,
+    collections::BTreeMap,
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    ffi::{CStr, OsStr, c_char, c_int, c_void},
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
+    rc::Rc,
 };
 
-use crate::luau;
+use crate::{luau, luaurc};
+
+mod data;
+mod plugin;
 
 fn ptr_to_str(stack: &luau::Stack, ptr: *const c_char) -> &str {
     unsafe {
@@ -14,6 +20,10 @@ fn ptr_to_str(stack: &luau::Stack, ptr: *const c_char) -> &str {
     }
 }
 
+fn ptr_to_path<'a>(ptr: *const c_char) -> &'a Path {
+    unsafe { Path::new(OsStr::from_bytes(CStr::from_ptr(ptr).to_bytes())) }
+}
+
 enum Reason {
     NotFound,
     Ambiguous,
@@ -29,64 +39,323 @@ impl From<Result<(), Reason>> for luau::ffi::luarequire_NavigateResult {
     }
 }
 
+/// Where `require` looks for modules beyond what .luaurc aliases provide.
+#[derive(Default, Clone)]
+pub struct Options {
+    roots: Vec<PathBuf>,
+    lua: bool,
+    plugins: BTreeMap<String, PathBuf>,
+    plugin_dirs: Vec<PathBuf>,
+    providers: BTreeMap<String, Rc<dyn luau::ModuleProvider>>,
+}
+
+impl Options {
+    /// Adds directories searched for `@name` requires that no .luaurc
+    /// declares an alias for.
+    pub fn with_roots(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.roots.extend(
+            roots
+                .into_iter()
+                .filter_map(|root| root.canonicalize().ok()),
+        );
+        self
+    }
+
+    /// Adds the search roots listed in the `BRE_PATH` environment variable.
+    pub fn with_env(self) -> Self {
+        match std::env::var_os("BRE_PATH") {
+            Some(paths) => self.with_roots(std::env::split_paths(&paths)),
+            None => self,
+        }
+    }
+
+    /// Falls back to `.lua` and `init.lua` files when no `.luau` module exists.
+    pub fn with_lua(mut self, lua: bool) -> Self {
+        self.lua = lua;
+        self
+    }
+
+    /// Registers the shared libraries `@plugin/name` requires load.
+    pub fn with_plugins(mut self, plugins: impl IntoIterator<Item = (String, PathBuf)>) -> Self {
+        self.plugins.extend(
+            plugins
+                .into_iter()
+                .map(|(name, path)| (name, path.canonicalize().unwrap_or(path))),
+        );
+        self
+    }
+
+    /// Adds directories searched for `@plugin/name` libraries that were not
+    /// registered explicitly.
+    pub fn with_plugin_dirs(mut self, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.plugin_dirs
+            .extend(dirs.into_iter().filter_map(|dir| dir.canonicalize().ok()));
+        self
+    }
+
+    /// Mounts `provider` so `@alias/path` requires are served by it.
+    pub fn with_provider(
+        mut self,
+        alias: &str,
+        provider: impl luau::ModuleProvider + 'static,
+    ) -> Self {
+        self.providers
+            .insert(alias.to_ascii_lowercase(), Rc::new(provider));
+        self
+    }
+
+    /// The provider mounted at the start of `path` and the rest of the path
+    /// within it.
+    fn provider(&self, path: &Path) -> Option<(&dyn luau::ModuleProvider, String)> {
+        let mut components = path.components();
+        let alias = components.next()?.as_os_str().to_str()?.strip_prefix('@')?;
+        let provider = self.providers.get(alias)?;
+
+        let path = components
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+
+        Some((provider.as_ref(), path))
+    }
+
+    fn plugin_candidates(&self, name: &str) -> Vec<PathBuf> {
+        if let Some(path) = self.plugins.get(name) {
+            return vec![path.clone()];
+        }
+
+        self.plugin_dirs
+            .iter()
+            .flat_map(|dir| {
+                [
+                    dir.join(format!("{DLL_PREFIX}{name}{DLL_SUFFIX}")),
+                    dir.join(format!("{name}{DLL_SUFFIX}")),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+fn candidates(path: &Path, lua: bool) -> Vec<Vec<PathBuf>> {
+    let mut groups = Vec::new();
+
+    // a path with a data extension may name the file itself
+    if data::Kind::of(path).is_some() {
+        groups.push(vec![path.to_owned()]);
+    }
+
+    groups.push(vec![with_suffix(path, ".luau"), path.join("init.luau")]);
+
+    if lua {
+        groups.push(vec![with_suffix(path, ".lua"), path.join("init.lua")]);
+    }
+
+    groups
+}
+
+/// The files `path` could refer to, from the first group of candidates where
+/// any exist, so `.luau` modules always shadow `.lua` ones.
+fn module_files(path: &Path, lua: bool) -> Vec<PathBuf> {
+    candidates(path, lua)
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>()
+        })
+        .find(|found| !found.is_empty())
+        .unwrap_or_default()
+}
+
+fn list(paths: impl IntoIterator<Item = PathBuf>) -> String {
+    paths
+        .into_iter()
+        .map(|p| format!("\n  {}", p.display()))
+        .collect()
+}
+
+struct State {
+    path: PathBuf,
+    requirer: PathBuf,
+    options: Options,
+}
+
 #[repr(transparent)]
 struct Current(*mut c_void);
 
 impl Current {
-    pub fn as_ptr(&self) -> *mut PathBuf {
+    pub fn as_ptr(&self) -> *mut State {
         self.0 as _
     }
 
-    pub fn as_path(&self) -> &Path {
+    fn state(&self) -> &State {
         unsafe { self.as_ptr().as_ref().unwrap_unchecked() }
     }
 
+    pub fn as_path(&self) -> &Path {
+        &self.state().path
+    }
+
     #[allow(clippy::mut_from_ref)]
     pub fn as_pathbuf(&self) -> &mut PathBuf {
-        unsafe { self.as_ptr().as_mut().unwrap_unchecked() }
+        unsafe { &mut self.as_ptr().as_mut().unwrap_unchecked().path }
     }
 
-    pub fn as_str(&self) -> &str {
-        self.as_path().to_str().expect("path is not valid utf8")
+    pub fn requirer(&self) -> &Path {
+        &self.state().requirer
     }
 
-    pub fn possible_paths(&self) -> (PathBuf, PathBuf) {
-        (
-            self.as_path().with_extension("luau"),
-            self.as_path().join("init.luau"),
-        )
+    pub fn options(&self) -> &Options {
+        &self.state().options
     }
 
-    pub fn exists(&self) -> bool {
-        let (first, second) = self.possible_paths();
+    /// The plugin being navigated to, for paths under `@plugin`.
+    pub fn plugin(&self) -> Option<&str> {
+        let name = self.as_path().strip_prefix(plugin::ROOT).ok()?.to_str()?;
+        (!name.is_empty()).then_some(name)
+    }
 
-        first.is_file() || second.is_file()
+    /// The provider serving the current path, for paths under a mounted alias.
+    pub fn provided(&self) -> Option<(&dyn luau::ModuleProvider, String)> {
+        self.options().provider(self.as_path())
+    }
+
+    pub fn is_dir(&self) -> bool {
+        if let Some((provider, path)) = self.provided() {
+            return provider.is_directory(&path);
+        }
+
+        self.as_path() == Path::new(plugin::ROOT) || self.as_path().is_dir()
+    }
+
+    pub fn possible_paths(&self) -> Vec<PathBuf> {
+        if self.provided().is_some() {
+            return vec![self.as_path().to_owned()];
+        }
+
+        if let Some(name) = self.plugin() {
+            return self.options().plugin_candidates(name);
+        }
+
+        candidates(self.as_path(), self.options().lua)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    pub fn module_files(&self) -> Vec<PathBuf> {
+        if let Some((provider, path)) = self.provided() {
+            return match provider.is_module(&path) {
+                true => vec![self.as_path().to_owned()],
+                false => Vec::new(),
+            };
+        }
+
+        if let Some(name) = self.plugin() {
+            let found = self.options().plugin_candidates(name);
+            return found.into_iter().filter(|p| p.is_file()).take(1).collect();
+        }
+
+        module_files(self.as_path(), self.options().lua)
+    }
+
+    pub fn module_file(&self) -> Option<PathBuf> {
+        self.module_files().into_iter().next()
+    }
+
+    pub fn exists(&self) -> bool {
+        !self.module_files().is_empty()
     }
 
     pub fn is_ambiguous(&self) -> bool {
-        let (first, second) = self.possible_paths();
+        self.module_files().len() > 1
+    }
 
-        first.is_file() && second.is_file()
+    pub fn not_found_message(&self) -> String {
+        let path = self.as_path();
+
+        // a file that exists but isn't a module or a data format we know
+        if let Some(ext) = path.extension().filter(|_| path.is_file()) {
+            return format!(
+                "module '{}' not found, '.{}' files can't be required, tried:{}",
+                path.display(),
+                ext.display(),
+                list(self.possible_paths())
+            );
+        }
+
+        format!(
+            "module '{}' not found, tried:{}",
+            path.display(),
+            list(self.possible_paths())
+        )
+    }
+
+    pub fn ambiguous_message(&self) -> String {
+        format!(
+            "module '{}' is ambiguous, it matches:{}",
+            self.as_path().display(),
+            list(self.module_files())
+        )
     }
 
-    pub fn jump(&self, path: &str) -> Result<(), Reason> {
-        match Path::new(path).canonicalize() {
-            Err(_) => Err(Reason::NotFound),
-            Ok(path) => {
-                self.as_pathbuf().clear();
-                self.as_pathbuf().push(path);
+    /// Finds `name` in the search roots, returning every path tried if it is
+    /// in none of them.
+    pub fn search(&self, name: &str) -> Result<PathBuf, Vec<PathBuf>> {
+        let lua = self.options().lua;
+        let mut tried = Vec::new();
 
-                Ok(())
+        for root in &self.options().roots {
+            let path = root.join(name);
+
+            if path.is_dir() || !module_files(&path, lua).is_empty() {
+                return Ok(path);
             }
+
+            tried.push(path);
+        }
+
+        Err(tried)
+    }
+
+    pub fn jump(&self, path: &Path) -> Result<(), Reason> {
+        let mounted = path == Path::new(plugin::ROOT) || self.options().provider(path).is_some();
+
+        if !path.is_absolute() && !mounted {
+            return Err(Reason::NotFound);
+        }
+
+        self.as_pathbuf().clear();
+        self.as_pathbuf().push(path);
+
+        if self.is_dir() || self.exists() {
+            Ok(())
+        } else {
+            Err(Reason::NotFound)
         }
     }
 
-    pub fn reset(&self, chunkname: &str) {
-        let chunkname = chunkname.strip_suffix(".luau").unwrap();
-        let chunkname = chunkname.strip_suffix("/init").unwrap_or(chunkname);
+    pub fn reset(&self, chunkname: &Path) {
+        let chunkname = chunkname.as_os_str().as_bytes();
+        let chunkname = chunkname
+            .strip_suffix(b".luau")
+            .or_else(|| chunkname.strip_suffix(b".lua"))
+            .unwrap_or(chunkname);
+
+        let chunkname = chunkname.strip_suffix(b"/init").unwrap_or(chunkname);
 
         self.as_pathbuf().clear();
-        self.as_pathbuf().push(chunkname);
+        self.as_pathbuf().push(OsStr::from_bytes(chunkname));
+
+        let state = unsafe { self.as_ptr().as_mut().unwrap_unchecked() };
+        state.requirer.clone_from(&state.path);
     }
 
     pub fn parent(&self) -> Result<(), Reason> {
@@ -99,10 +368,10 @@ impl Current {
         }
     }
 
-    pub fn child(&self, name: &str) -> Result<(), Reason> {
+    pub fn child(&self, name: &Path) -> Result<(), Reason> {
         self.as_pathbuf().push(name);
 
-        if !self.exists() {
+        if !self.is_dir() && !self.exists() {
             Err(Reason::NotFound)
         } else if self.is_ambiguous() {
             Err(Reason::Ambiguous)
@@ -111,12 +380,21 @@ impl Current {
         }
     }
 
-    pub fn config_path(&self) -> PathBuf {
-        self.as_path().join(".luaurc")
-    }
+    /// Turns a navigation result into an error that names every candidate
+    /// path involved.
+    pub fn navigate(
+        &self,
+        stack: &luau::Stack,
+        result: Result<(), Reason>,
+    ) -> luau::ffi::luarequire_NavigateResult {
+        match result {
+            Err(Reason::NotFound) if self.as_path().file_name().is_some() => {
+                stack.push_error(self.not_found_message())
+            }
 
-    pub fn config_exists(&self) -> bool {
-        self.config_path().is_file()
+            Err(Reason::Ambiguous) => stack.push_error(self.ambiguous_message()),
+            result => result.into(),
+        }
     }
 }
 
@@ -156,28 +434,31 @@ extern "C-unwind" fn is_require_allowed(_: luau::Context, _: Current, _: *const
 }
 
 extern "C-unwind" fn reset(
-    ctx: luau::Context,
+    _: luau::Context,
     current: Current,
     chunkname: *const c_char,
 ) -> luau::ffi::luarequire_NavigateResult {
-    current.reset(ptr_to_str(&ctx, chunkname));
+    current.reset(ptr_to_path(chunkname));
 
     luau::ffi::luarequire_NavigateResult::NAVIGATE_SUCCESS
 }
 
 extern "C-unwind" fn jump_to_alias(
-    ctx: luau::Context,
+    _: luau::Context,
     current: Current,
     path: *const c_char,
 ) -> luau::ffi::luarequire_NavigateResult {
-    current.jump(ptr_to_str(&ctx, path)).into()
+    current.jump(ptr_to_path(path)).into()
 }
 
 extern "C-unwind" fn to_parent(
-    _: luau::Context,
+    ctx: luau::Context,
     current: Current,
 ) -> luau::ffi::luarequire_NavigateResult {
-    current.parent().into()
+    match current.parent() {
+        Err(Reason::NotFound) => luau::ffi::luarequire_NavigateResult::NAVIGATE_NOT_FOUND,
+        result => current.navigate(&ctx, result),
+    }
 }
 
 extern "C-unwind" fn to_child(
@@ -185,11 +466,16 @@ extern "C-unwind" fn to_child(
     current: Current,
     name: *const c_char,
 ) -> luau::ffi::luarequire_NavigateResult {
-    current.child(ptr_to_str(&ctx, name)).into()
+    let result = current.child(ptr_to_path(name));
+    current.navigate(&ctx, result)
 }
 
-extern "C-unwind" fn is_module_present(_: luau::Context, current: Current) -> bool {
-    current.exists()
+extern "C-unwind" fn is_module_present(ctx: luau::Context, current: Current) -> bool {
+    if current.is_ambiguous() {
+        ctx.push_error(current.ambiguous_message());
+    }
+
+    current.exists() || ctx.push_error(current.not_found_message())
 }
 
 extern "C-unwind" fn get_chunkname(
@@ -200,7 +486,11 @@ extern "C-unwind" fn get_chunkname(
     size_out: *mut usize,
 ) -> luau::ffi::luarequire_WriteResult {
     let writer = Writer::new(buffer, buffer_size, size_out);
-    let path = current.as_str().as_bytes();
+    let Some(path) = current.module_file() else {
+        return luau::ffi::luarequire_WriteResult::WRITE_FAILURE;
+    };
+
+    let path = path.as_os_str().as_encoded_bytes();
 
     let Ok(slice) = writer.set_size(path.len()) else {
         return luau::ffi::luarequire_WriteResult::WRITE_BUFFER_TOO_SMALL;
@@ -230,36 +520,73 @@ extern "C-unwind" fn get_cache_key(
     get_chunkname(ctx, current, buffer, buffer_size, size_out)
 }
 
-extern "C-unwind" fn is_config_present(_: &luau::Context, current: Current) -> bool {
-    current.config_exists()
+extern "C-unwind" fn is_config_present(_: luau::Context, _: Current) -> bool {
+    // get_alias searches every .luaurc above the requirer itself, so the
+    // navigator never has to walk up to find one.
+    true
 }
 
-extern "C-unwind" fn get_config(
-    _: luau::Context,
+extern "C-unwind" fn get_alias(
+    ctx: luau::Context,
     current: Current,
+    alias: *const c_char,
     buffer: *mut c_char,
     buffer_size: usize,
     size_out: *mut usize,
 ) -> luau::ffi::luarequire_WriteResult {
     let writer = Writer::new(buffer, buffer_size, size_out);
-    let path = current.config_path();
+    let alias = ptr_to_str(&ctx, alias);
+
+    let target = if alias.eq_ignore_ascii_case("self") {
+        current.requirer().to_owned()
+    } else if alias.eq_ignore_ascii_case("plugin") {
+        PathBuf::from(plugin::ROOT)
+    } else if let Some((alias, _)) = current
+        .options()
+        .providers
+        .get_key_value(&alias.to_ascii_lowercase())
+    {
+        PathBuf::from(format!("@{alias}"))
+    } else {
+        match luaurc::find_alias(current.requirer(), alias) {
+            Ok(Some(found))
+                if found.target.is_dir()
+                    || !module_files(&found.target, current.options().lua).is_empty() =>
+            {
+                found.target
+            }
 
-    let Ok(mut file) = std::fs::File::open(path) else {
-        return luau::ffi::luarequire_WriteResult::WRITE_FAILURE;
+            Ok(Some(found)) => ctx.push_error(format!(
+                "alias '@{}' declared in '{}' points to '{}', which does not exist",
+                found.name,
+                found.config.display(),
+                found.target.display()
+            )),
+
+            Ok(None) => current.search(alias).unwrap_or_else(|tried| {
+                ctx.push_error(format!(
+                    "unknown alias '@{alias}', no {} above '{}' declares it and it is not in any search path{}",
+                    luaurc::LUAURC,
+                    current.requirer().display(),
+                    if tried.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", tried:{}", list(tried))
+                    }
+                ))
+            }),
+
+            Err(e) => ctx.push_error(e),
+        }
     };
 
-    let Ok(len) = file.metadata().map(|m| m.len() as usize) else {
-        return luau::ffi::luarequire_WriteResult::WRITE_FAILURE;
-    };
+    let target = target.as_os_str().as_encoded_bytes();
 
-    let Ok(slice) = writer.set_size(len) else {
+    let Ok(slice) = writer.set_size(target.len()) else {
         return luau::ffi::luarequire_WriteResult::WRITE_BUFFER_TOO_SMALL;
     };
 
-    let Ok(_) = file.read_exact(slice) else {
-        return luau::ffi::luarequire_WriteResult::WRITE_FAILURE;
-    };
-
+    slice.copy_from_slice(target);
     luau::ffi::luarequire_WriteResult::WRITE_SUCCESS
 }
 
@@ -273,20 +600,144 @@ fn push_yield_table(stack: &luau::Stack) {
     stack.table_get_raw(luau::REGISTRY_IDX);
 }
 
-extern "C-unwind" fn runner(ctx: luau::Context) -> luau::FnReturn {
-    // chunkname, userfunc
+fn push_loading_key(stack: &luau::Stack) {
+    static mut REG_LOADING_KEY: u8 = b'l';
+    stack.push_light_userdata(&raw mut REG_LOADING_KEY as *mut _ as _);
+}
+
+/// Pushes the table mapping each runner thread to the module it is loading.
+fn push_loading_table(stack: &luau::Stack) {
+    push_loading_key(stack);
+    stack.table_get_raw(luau::REGISTRY_IDX);
+}
+
+/// Removes `thread` from the requirers waiting on modules that are still
+/// loading, so a cancelled thread isn't resumed when they finish.
+pub(crate) fn cancel(stack: &luau::Stack, thread: &luau::Thread) {
+    push_yield_table(stack); // reqtbl
+    stack.push_nil(); // reqtbl, nil
+
+    while stack.next(-2) {
+        // reqtbl, chunkname, yldtbl
+        let len = stack.len(-1) as u32;
+        let mut kept = 0;
+
+        for i in 1..=len {
+            stack.table_get_raw_i(-1, i); // reqtbl, chunkname, yldtbl, waiter
+
+            match stack.to_thread(-1) {
+                Some(waiter) if waiter.as_ptr() == thread.as_ptr() => stack.pop(1),
 
-    push_yield_table(&ctx); // chunkname, userfunc, reqtbl
-    ctx.push_copy(1); // chunkname, userfunc, reqtbl, chunkname
-    ctx.push_table(); // chunkname, userfunc, reqtbl, chunkname, yldtbl
-    ctx.table_set_raw(3); // chunkname, userfunc, reqtbl
-    ctx.pop(1); // chunkname, userfunc
+                _ => {
+                    kept += 1;
+                    stack.table_set_raw_i(-2, kept); // reqtbl, chunkname, yldtbl
+                }
+            }
+        }
+
+        for i in kept + 1..=len {
+            stack.push_nil(); // reqtbl, chunkname, yldtbl, nil
+            stack.table_set_raw_i(-2, i); // reqtbl, chunkname, yldtbl
+        }
+
+        stack.pop(1); // reqtbl, chunkname
+    }
+
+    stack.pop(1);
+}
+
+/// Walks the requirers of the module the current thread is loading, returning
+/// the full chain if `chunkname` is one of them.
+fn find_cycle(stack: &luau::Stack, chunkname: &[u8]) -> Option<String> {
+    push_yield_table(stack); // reqtbl
+    push_loading_table(stack); // reqtbl, loading
+    stack.push_thread(&stack.thread()); // reqtbl, loading, thread
+    stack.table_get_raw(-2); // reqtbl, loading, requirer
+    stack.remove(-2); // reqtbl, requirer
+
+    let mut chain = vec![chunkname.to_vec()];
+    let mut cycle = false;
+
+    while let Some(name) = stack.to_string_slice(-1) {
+        chain.push(name.to_vec());
+
+        if name == chunkname {
+            cycle = true;
+            break;
+        }
+
+        stack.table_get_raw(-2); // reqtbl, yldtbl
 
+        if !stack.is_table(-1) {
+            break;
+        }
+
+        stack.table_get_raw_field(-1, c"requirer"); // reqtbl, yldtbl, requirer
+        stack.remove(-2); // reqtbl, requirer
+    }
+
+    stack.pop(2); // stack is empty
+
+    cycle.then(|| {
+        chain
+            .iter()
+            .rev()
+            .map(|name| String::from_utf8_lossy(name))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    })
+}
+
+const CHAIN_HEADER: &str = "require chain:";
+
+/// Extends the error note left by a failure while loading `chunkname`: the
+/// module's own traceback, or the chain of a nested require that failed.
+fn chain_note(chunkname: &str, note: Option<String>) -> String {
+    match note {
+        Some(note) if note.contains(CHAIN_HEADER) => {
+            format!("{}\n  required by {chunkname}", note.trim_end())
+        }
+
+        Some(trace) => format!(
+            "while requiring '{chunkname}':\n{}\n{CHAIN_HEADER}\n  {chunkname}",
+            trace.trim_end()
+        ),
+
+        None => format!("{CHAIN_HEADER}\n  {chunkname}"),
+    }
+}
+
+/// Prefixes an error raised while loading `chunkname`. Errors passing
+/// through nested requires collect one prefix per module.
+fn chain_error(chunkname: &str, err: &str) -> String {
+    format!("while requiring '{chunkname}': {}", err.trim_end())
+}
+
+/// Raises an error for `chunkname` failing before its body ran, noting the
+/// require chain on the requirer.
+fn raise(ctx: &luau::Context, chunkname: &str, err: &str) -> ! {
+    ctx.push_string(chain_error(chunkname, err));
+
+    let note = chain_note(chunkname, None);
+    ctx.main().set_error_note(&ctx.thread(), ctx, -1, note);
+
+    ctx.error()
+}
+
+extern "C-unwind" fn runner(ctx: luau::Context) -> luau::FnReturn {
     // chunkname, userfunc
 
+    // the traceback is noted beside the error, for the report if nobody
+    // catches it
     extern "C-unwind" fn handle_error(ctx: luau::Context) -> luau::FnReturn {
         let main = ctx.main();
-        main.handle_status(&ctx.thread(), luau::Status::ErrRuntime);
+        let thread = ctx.thread();
+
+        // errors from nested requires already carry their chain
+        let note = main
+            .take_error_note(&thread, &ctx, 1)
+            .unwrap_or_else(|| format!("stack traceback:\n{}", thread.traceback()));
+        main.set_error_note(&thread, &ctx, 1, note);
 
         ctx.ret_with(1)
     }
@@ -296,7 +747,6 @@ extern "C-unwind" fn runner(ctx: luau::Context) -> luau::FnReturn {
     ctx.replace(2); // chunkname, errfunc, userfunc
 
     let success = ctx.pcall(0, 1, 2); // chunkname, errfunc, result
-    ctx.remove(-2); // chunkname, result
 
     if ctx.thread().status() == luau::Status::Yield {
         -1 // special yield indicator
@@ -306,27 +756,43 @@ extern "C-unwind" fn runner(ctx: luau::Context) -> luau::FnReturn {
 }
 
 extern "C-unwind" fn runner_cont(ctx: luau::Context, status: luau::Status) -> luau::FnReturn {
-    // chunkname, result
+    // chunkname, errfunc, result
+
+    ctx.remove(2); // chunkname, result
 
     let main = ctx.main();
+    let ok = status == luau::Status::Ok;
+
+    let note = (!ok).then(|| {
+        let chunkname = String::from_utf8_lossy(ctx.to_string_slice(1).unwrap()).into_owned();
+        let note = chain_note(&chunkname, main.take_error_note(&ctx.thread(), &ctx, 2));
+
+        // requirers see which require failed, even when they catch it
+        let err = chain_error(&chunkname, &ctx.to_display(2));
+        ctx.push_string(err); // chunkname, result, err
+        ctx.replace(2); // chunkname, err
+
+        note
+    });
 
     push_yield_table(&ctx); // chunkname, result, reqtbl
     ctx.push_copy(1); // chunkname, result, reqtbl, chunkname
     ctx.table_get_raw(3); // chunkname, result, reqtbl, yldtbl
 
-    for i in 1..ctx.len(-1) {
+    for i in 1..=ctx.len(-1) {
         ctx.table_get_raw_i(-1, i as u32); // chunkname, result, reqyld, yldtbl, thread
         let thread = ctx.to_thread(-1).unwrap();
         ctx.pop(1); // chunkname, result, reqyld, yldtbl
 
-        if status == luau::Status::Ok {
-            ctx.xpush(&thread, 2);
-            main.spawn(&thread, 1);
-        } else {
-            thread
-                .stack()
-                .push_string("required module errored while loading");
-            main.spawn_error(&thread)
+        ctx.xpush(&thread, 2);
+
+        match &note {
+            None => main.spawn(&thread, 1),
+
+            Some(note) => {
+                main.set_error_note(&thread, &ctx, 2, note.clone());
+                main.spawn_error(&thread)
+            }
         }
     }
 
@@ -338,11 +804,23 @@ extern "C-unwind" fn runner_cont(ctx: luau::Context, status: luau::Status) -> lu
 
     ctx.pop(1); // chunkname, result
 
-    if status == luau::Status::Ok {
-        ctx.ret_with(1)
-    } else {
-        ctx.error()
+    push_loading_table(&ctx); // chunkname, result, loading
+    ctx.push_thread(&ctx.thread()); // chunkname, result, loading, thread
+    ctx.push_nil(); // chunkname, result, loading, thread, nil
+    ctx.table_set_raw(-3); // chunkname, result, loading
+    ctx.pop(1); // chunkname, result
+
+    // left for `load` to hand to the requirer that started the module
+    if let Some(note) = note {
+        main.set_error_note(&ctx.thread(), &ctx, 2, note);
     }
+
+    // failures are handed to the requirers instead of erroring this thread,
+    // so the scheduler does not report them a second time
+    ctx.push_boolean(ok); // chunkname, result, ok
+    ctx.insert(2); // chunkname, ok, result
+
+    ctx.ret_with(2)
 }
 
 extern "C-unwind" fn load(
@@ -353,47 +831,116 @@ extern "C-unwind" fn load(
     _: *const c_char,
 ) -> c_int {
     let chunkname = unsafe { CStr::from_ptr(chunkname) };
+    let name = String::from_utf8_lossy(chunkname.to_bytes()).into_owned();
 
     push_yield_table(&ctx); // reqtbl
     ctx.push_string(chunkname.to_bytes()); // reqtbl, chunkname
     ctx.table_get_raw(-2); // reqtbl, yldtbl
 
     if !ctx.is_nil(-1) {
+        if let Some(cycle) = find_cycle(&ctx, chunkname.to_bytes()) {
+            ctx.push_error(format!("require cycle detected: {cycle}"));
+        }
+
         ctx.push_thread(&ctx.thread()); // reqtbl, yldtbl, thread
         ctx.table_set_raw_i(-2, ctx.len(-2) as u32 + 1); // reqtbl, yldtbl
+        ctx.pop(2); // stack is empty
 
+        ctx.main().suspend(&ctx.thread());
         return -1; // special yield indicator
     }
 
     ctx.pop(2); // stack is empty
 
-    let source = {
-        let (first, second) = current.possible_paths();
+    let path = current
+        .module_file()
+        .unwrap_or_else(|| ctx.push_error(current.not_found_message()));
 
-        if first.exists() {
-            std::fs::read(&first).unwrap_or_else(|_| {
-                ctx.push_error(format!("failed to read file '{}'", first.display()))
-            })
-        } else {
-            std::fs::read(&second).unwrap_or_else(|_| {
-                ctx.push_error(format!("failed to read file '{}'", second.display()))
-            })
+    if current.plugin().is_some() {
+        if let Err(err) = plugin::open(&ctx, &path) {
+            raise(&ctx, &name, &err);
+        }
+
+        return 0;
+    }
+
+    let module = match current.provided() {
+        Some((provider, path)) => provider
+            .load(&path)
+            .unwrap_or_else(|err| raise(&ctx, &name, &err)),
+
+        None => {
+            let source = std::fs::read(&path).unwrap_or_else(|_| {
+                ctx.push_error(format!("failed to read file '{}'", path.display()))
+            });
+
+            if let Some(kind) = data::Kind::of(&path) {
+                if let Err(err) = data::push(&ctx, kind, &source) {
+                    raise(&ctx, &name, &format!("failed to parse data: {err}"));
+                }
+
+                return 0;
+            }
+
+            luau::Module::Source(source)
         }
     };
 
     let main = ctx.main();
+    let bytecode = match module {
+        luau::Module::Source(source) => main.compiler().compile(&source),
+        luau::Module::Bytecode(bytecode) => bytecode,
+        luau::Module::Value(push) => {
+            push(ctx.thread().stack());
+            return 0;
+        }
+    };
+
+    if let Some(err) = bytecode.error() {
+        raise(&ctx, &name, &format!("{name}{err}"));
+    }
+
     let (_, thread) = main.new_thread();
     let stack = thread.stack();
 
     stack.push_function_cont(c"require_runner", runner, runner_cont);
     stack.push_string(chunkname.to_bytes());
-    stack.push_bytecode(chunkname, &main.compiler().compile(&source));
+    stack.push_bytecode(chunkname, &bytecode);
+
+    if !stack.is_function(-1) {
+        let err = stack.to_string_str(-1).unwrap_or("invalid bytecode");
+        raise(&ctx, &name, err);
+    }
+
+    push_loading_table(&ctx); // loading
+    ctx.push_thread(&ctx.thread()); // loading, thread
+    ctx.table_get_raw(-2); // loading, requirer
+    ctx.push_thread(&thread); // loading, requirer, runner
+    ctx.push_string(chunkname.to_bytes()); // loading, requirer, runner, chunkname
+    ctx.table_set_raw(-4); // loading, requirer
+
+    push_yield_table(&ctx); // loading, requirer, reqtbl
+    ctx.push_string(chunkname.to_bytes()); // loading, requirer, reqtbl, chunkname
+    ctx.push_table(); // loading, requirer, reqtbl, chunkname, yldtbl
+    ctx.push_copy(-4); // loading, requirer, reqtbl, chunkname, yldtbl, requirer
+    ctx.table_set_raw_field(-2, c"requirer"); // loading, requirer, reqtbl, chunkname, yldtbl
+    ctx.table_set_raw(-3); // loading, requirer, reqtbl
+    ctx.pop(3); // stack is empty
 
     match thread.resume(None, 2) {
         luau::Status::Ok => {
+            // chunkname, ok, result
             stack.xpush(&ctx.thread(), -1);
 
-            0
+            if stack.to_boolean(-2) == Some(true) {
+                return 0;
+            }
+
+            if let Some(note) = main.take_error_note(&thread, &ctx, -1) {
+                main.set_error_note(&ctx.thread(), &ctx, -1, note);
+            }
+
+            ctx.error()
         }
 
         luau::Status::Yield => {
@@ -402,15 +949,20 @@ extern "C-unwind" fn load(
             ctx.table_get_raw(-2); // reqtbl, yldtbl
             ctx.push_thread(&ctx.thread()); // reqtbl, yldtbl, thread
             ctx.table_set_raw_i(-2, ctx.len(-2) as u32 + 1); // reqtbl, yldtbl
+            ctx.pop(2); // stack is empty
 
+            ctx.main().suspend(&ctx.thread());
             -1
         }
 
-        _ => ctx.push_error("required module errored while loading"),
+        _ => {
+            let err = stack.to_string_str(-1).unwrap_or("unknown error");
+            raise(&ctx, &name, err)
+        }
     }
 }
 
-pub fn open(main: luau::Main) {
+pub fn open(main: luau::Main, options: Options) {
     #[allow(clippy::missing_transmute_annotations)]
     extern "C-unwind" fn luarequire_configuration_init(
         config: *mut luau::ffi::luarequire_Configuration,
@@ -428,7 +980,8 @@ pub fn open(main: luau::Main) {
             (*config).get_loadname = transmute(get_loadname as *mut c_void);
             (*config).get_cache_key = transmute(get_cache_key as *mut c_void);
             (*config).is_config_present = transmute(is_config_present as *mut c_void);
-            (*config).get_config = transmute(get_config as *mut c_void);
+            (*config).get_alias = transmute(get_alias as *mut c_void);
+            (*config).get_config = None;
             (*config).load = transmute(load as *mut c_void);
         }
     }
@@ -439,11 +992,19 @@ pub fn open(main: luau::Main) {
     stack.push_table();
     stack.table_set_raw(luau::REGISTRY_IDX);
 
+    push_loading_key(&stack);
+    stack.push_table();
+    stack.table_set_raw(luau::REGISTRY_IDX);
+
     unsafe {
         luau::ffi::luaopen_require(
             main.as_ptr(),
             luarequire_configuration_init,
-            Box::into_raw(Box::new(PathBuf::new())) as *mut c_void,
+            Box::into_raw(Box::new(State {
+                path: PathBuf::new(),
+                requirer: PathBuf::new(),
+                options,
+            })) as *mut c_void,
         );
     }
 }
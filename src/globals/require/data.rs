@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use crate::{libs::codec, luau};
+
+/// How a required file that is not a Luau module is turned into a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Json,
+    Toml,
+    Yaml,
+    Text,
+    Binary,
+}
+
+/// Extensions loaded as raw buffers.
+const BINARY: &[&str] = &[
+    "bin", "dat", "wasm", "png", "jpg", "jpeg", "gif", "webp", "ico",
+];
+
+impl Kind {
+    /// The kind of data module `path` is, or `None` if it names Luau source,
+    /// has no extension or has one that isn't known.
+    pub fn of(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "json" => Some(Kind::Json),
+            "toml" => Some(Kind::Toml),
+            "yaml" | "yml" => Some(Kind::Yaml),
+            "txt" => Some(Kind::Text),
+            ext if BINARY.contains(&ext) => Some(Kind::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes `data` according to `kind` and pushes the result onto the stack.
+pub fn push(stack: &luau::Stack, kind: Kind, data: &[u8]) -> Result<(), String> {
+    let format = match kind {
+        Kind::Text => {
+            stack.push_string(data);
+            return Ok(());
+        }
+
+        Kind::Binary => {
+            stack.push_buffer(data.len()).copy_from_slice(data);
+            return Ok(());
+        }
+
+        Kind::Json => codec::Format::Json,
+        Kind::Toml => codec::Format::Toml,
+        Kind::Yaml => codec::Format::Yaml,
+    };
+
+    codec::push(stack, &format.decode(data)?);
+    Ok(())
+}
@@ -0,0 +1,323 @@
+use std::{
+    ffi::{c_char, c_double, c_int, c_void},
+    path::Path,
+};
+
+use crate::luau::{
+    self,
+    ffi::{lua_CFunction, lua_State},
+};
+
+/// The virtual directory `@plugin/name` requires navigate through.
+pub const ROOT: &str = "@plugin";
+
+/// The symbol every plugin exports, see `include/bre.h`.
+const ENTRY: &[u8] = b"bre_plugin_open\0";
+
+/// Bumped whenever [`Api`] changes in a way old plugins cannot handle. New
+/// functions are only ever appended, so plugins built against an older
+/// version keep working.
+pub const ABI_VERSION: u32 = 1;
+
+type Open = unsafe extern "C-unwind" fn(api: *const Api, state: *mut lua_State) -> c_int;
+
+type Dtor = unsafe extern "C" fn(*mut c_void);
+
+/// The functions a plugin uses to talk to the runtime, passed to
+/// `bre_plugin_open` instead of exposing the Luau C API directly.
+#[repr(C)]
+pub struct Api {
+    pub version: u32,
+
+    pub get_top: unsafe extern "C-unwind" fn(*mut lua_State) -> c_int,
+    pub set_top: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub push_copy: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub remove: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub insert: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub type_of: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub raw_equal: unsafe extern "C-unwind" fn(*mut lua_State, c_int, c_int) -> c_int,
+
+    pub push_nil: unsafe extern "C-unwind" fn(*mut lua_State),
+    pub push_boolean: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub push_number: unsafe extern "C-unwind" fn(*mut lua_State, c_double),
+    pub push_string: unsafe extern "C-unwind" fn(*mut lua_State, *const c_char, usize),
+    pub push_table: unsafe extern "C-unwind" fn(*mut lua_State),
+    pub push_function: unsafe extern "C-unwind" fn(*mut lua_State, *const c_char, lua_CFunction),
+    pub push_closure:
+        unsafe extern "C-unwind" fn(*mut lua_State, *const c_char, lua_CFunction, c_int),
+    pub push_light_userdata: unsafe extern "C-unwind" fn(*mut lua_State, *mut c_void),
+    pub push_userdata:
+        unsafe extern "C-unwind" fn(*mut lua_State, usize, Option<Dtor>) -> *mut c_void,
+
+    pub get_field: unsafe extern "C-unwind" fn(*mut lua_State, c_int, *const c_char) -> c_int,
+    pub set_field: unsafe extern "C-unwind" fn(*mut lua_State, c_int, *const c_char),
+    pub get_table: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub set_table: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub get_index: unsafe extern "C-unwind" fn(*mut lua_State, c_int, c_int) -> c_int,
+    pub set_index: unsafe extern "C-unwind" fn(*mut lua_State, c_int, c_int),
+    pub next: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub len: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> usize,
+    pub push_metatable: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub set_metatable: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+
+    pub to_boolean: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub to_number: unsafe extern "C-unwind" fn(*mut lua_State, c_int, *mut c_int) -> c_double,
+    pub to_string: unsafe extern "C-unwind" fn(*mut lua_State, c_int, *mut usize) -> *const c_char,
+    pub to_userdata: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> *mut c_void,
+
+    pub new_ref: unsafe extern "C-unwind" fn(*mut lua_State, c_int) -> c_int,
+    pub push_ref: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+    pub unref: unsafe extern "C-unwind" fn(*mut lua_State, c_int),
+
+    pub call: unsafe extern "C-unwind" fn(*mut lua_State, c_int, c_int),
+    pub pcall: unsafe extern "C-unwind" fn(*mut lua_State, c_int, c_int) -> c_int,
+    pub error: unsafe extern "C-unwind" fn(*mut lua_State, *const c_char, usize) -> !,
+}
+
+unsafe extern "C-unwind" fn get_top(l: *mut lua_State) -> c_int {
+    unsafe { luau::ffi::lua_gettop(l) }
+}
+
+unsafe extern "C-unwind" fn set_top(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_settop(l, idx) }
+}
+
+unsafe extern "C-unwind" fn push_copy(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_pushvalue(l, idx) }
+}
+
+unsafe extern "C-unwind" fn remove(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_remove(l, idx) }
+}
+
+unsafe extern "C-unwind" fn insert(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_insert(l, idx) }
+}
+
+unsafe extern "C-unwind" fn type_of(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_type(l, idx) as c_int }
+}
+
+unsafe extern "C-unwind" fn raw_equal(l: *mut lua_State, idx1: c_int, idx2: c_int) -> c_int {
+    unsafe { luau::ffi::lua_rawequal(l, idx1, idx2) }
+}
+
+unsafe extern "C-unwind" fn push_nil(l: *mut lua_State) {
+    unsafe { luau::ffi::lua_pushnil(l) }
+}
+
+unsafe extern "C-unwind" fn push_boolean(l: *mut lua_State, b: c_int) {
+    unsafe { luau::ffi::lua_pushboolean(l, b) }
+}
+
+unsafe extern "C-unwind" fn push_number(l: *mut lua_State, n: c_double) {
+    unsafe { luau::ffi::lua_pushnumber(l, n) }
+}
+
+unsafe extern "C-unwind" fn push_string(l: *mut lua_State, s: *const c_char, len: usize) {
+    unsafe { luau::ffi::lua_pushlstring(l, s, len) }
+}
+
+unsafe extern "C-unwind" fn push_table(l: *mut lua_State) {
+    unsafe { luau::ffi::lua_createtable(l, 0, 0) }
+}
+
+unsafe extern "C-unwind" fn push_function(
+    l: *mut lua_State,
+    name: *const c_char,
+    f: lua_CFunction,
+) {
+    unsafe { luau::ffi::lua_pushcclosurek(l, f, name, 0, None) }
+}
+
+unsafe extern "C-unwind" fn push_closure(
+    l: *mut lua_State,
+    name: *const c_char,
+    f: lua_CFunction,
+    nup: c_int,
+) {
+    unsafe { luau::ffi::lua_pushcclosurek(l, f, name, nup, None) }
+}
+
+unsafe extern "C-unwind" fn push_light_userdata(l: *mut lua_State, p: *mut c_void) {
+    unsafe { luau::ffi::lua_pushlightuserdatatagged(l, p, 0) }
+}
+
+unsafe extern "C-unwind" fn push_userdata(
+    l: *mut lua_State,
+    size: usize,
+    dtor: Option<Dtor>,
+) -> *mut c_void {
+    unsafe {
+        match dtor {
+            Some(dtor) => luau::ffi::lua_newuserdatadtor(l, size, dtor),
+            None => luau::ffi::lua_newuserdatatagged(l, size, 0),
+        }
+    }
+}
+
+// table access is raw, so it never runs metamethods or errors on a table
+
+unsafe extern "C-unwind" fn get_field(l: *mut lua_State, idx: c_int, key: *const c_char) -> c_int {
+    unsafe { luau::ffi::lua_rawgetfield(l, idx, key) }
+}
+
+unsafe extern "C-unwind" fn set_field(l: *mut lua_State, idx: c_int, key: *const c_char) {
+    unsafe { luau::ffi::lua_rawsetfield(l, idx, key) }
+}
+
+unsafe extern "C-unwind" fn get_table(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_rawget(l, idx) }
+}
+
+unsafe extern "C-unwind" fn set_table(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_rawset(l, idx) }
+}
+
+unsafe extern "C-unwind" fn get_index(l: *mut lua_State, idx: c_int, n: c_int) -> c_int {
+    unsafe { luau::ffi::lua_rawgeti(l, idx, n) }
+}
+
+unsafe extern "C-unwind" fn set_index(l: *mut lua_State, idx: c_int, n: c_int) {
+    unsafe { luau::ffi::lua_rawseti(l, idx, n) }
+}
+
+unsafe extern "C-unwind" fn next(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_next(l, idx) }
+}
+
+unsafe extern "C-unwind" fn len(l: *mut lua_State, idx: c_int) -> usize {
+    unsafe { luau::ffi::lua_objlen(l, idx) }
+}
+
+unsafe extern "C-unwind" fn push_metatable(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_getmetatable(l, idx) }
+}
+
+unsafe extern "C-unwind" fn set_metatable(l: *mut lua_State, idx: c_int) {
+    unsafe { luau::ffi::lua_setmetatable(l, idx) };
+}
+
+unsafe extern "C-unwind" fn to_boolean(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_toboolean(l, idx) }
+}
+
+unsafe extern "C-unwind" fn to_number(
+    l: *mut lua_State,
+    idx: c_int,
+    isnum: *mut c_int,
+) -> c_double {
+    unsafe { luau::ffi::lua_tonumberx(l, idx, isnum) }
+}
+
+unsafe extern "C-unwind" fn to_string(
+    l: *mut lua_State,
+    idx: c_int,
+    len: *mut usize,
+) -> *const c_char {
+    unsafe { luau::ffi::lua_tolstring(l, idx, len) }
+}
+
+unsafe extern "C-unwind" fn to_userdata(l: *mut lua_State, idx: c_int) -> *mut c_void {
+    unsafe { luau::ffi::lua_touserdata(l, idx) }
+}
+
+unsafe extern "C-unwind" fn new_ref(l: *mut lua_State, idx: c_int) -> c_int {
+    unsafe { luau::ffi::lua_ref(l, idx) }
+}
+
+unsafe extern "C-unwind" fn push_ref(l: *mut lua_State, r: c_int) {
+    unsafe { luau::ffi::lua_rawgeti(l, luau::ffi::LUA_REGISTRYINDEX, r) };
+}
+
+unsafe extern "C-unwind" fn unref(l: *mut lua_State, r: c_int) {
+    unsafe { luau::ffi::lua_unref(l, r) }
+}
+
+unsafe extern "C-unwind" fn call(l: *mut lua_State, nargs: c_int, nresults: c_int) {
+    unsafe { luau::ffi::lua_call(l, nargs, nresults) }
+}
+
+/// Leaves the error on the stack and returns a non-zero status if the call
+/// fails.
+unsafe extern "C-unwind" fn pcall(l: *mut lua_State, nargs: c_int, nresults: c_int) -> c_int {
+    unsafe { luau::ffi::lua_pcall(l, nargs, nresults, 0) as c_int }
+}
+
+unsafe extern "C-unwind" fn error(l: *mut lua_State, msg: *const c_char, len: usize) -> ! {
+    unsafe {
+        luau::ffi::lua_pushlstring(l, msg, len);
+        luau::ffi::lua_error(l)
+    }
+}
+
+static API: Api = Api {
+    version: ABI_VERSION,
+    get_top,
+    set_top,
+    push_copy,
+    remove,
+    insert,
+    type_of,
+    raw_equal,
+    push_nil,
+    push_boolean,
+    push_number,
+    push_string,
+    push_table,
+    push_function,
+    push_closure,
+    push_light_userdata,
+    push_userdata,
+    get_field,
+    set_field,
+    get_table,
+    set_table,
+    get_index,
+    set_index,
+    next,
+    len,
+    push_metatable,
+    set_metatable,
+    to_boolean,
+    to_number,
+    to_string,
+    to_userdata,
+    new_ref,
+    push_ref,
+    unref,
+    call,
+    pcall,
+    error,
+};
+
+/// Loads the shared library at `path` and pushes the value its
+/// `bre_plugin_open` returns.
+pub fn open(stack: &luau::Stack, path: &Path) -> Result<(), String> {
+    let library = unsafe { libloading::Library::new(path) }
+        .map_err(|e| format!("failed to load plugin '{}': {e}", path.display()))?;
+
+    let open = unsafe { library.get::<Open>(ENTRY) }.map_err(|_| {
+        format!(
+            "plugin '{}' does not export bre_plugin_open",
+            path.display()
+        )
+    })?;
+
+    let top = stack.get_top();
+    let pushed = unsafe { open(&API, stack.as_ptr()) };
+
+    if pushed != 1 || stack.get_top() != top + 1 {
+        stack.set_top(top);
+
+        return Err(format!(
+            "plugin '{}' must push exactly one value, it returned {pushed}",
+            path.display()
+        ));
+    }
+
+    // the functions it pushed live in the library, so it can never be closed
+    std::mem::forget(library);
+
+    Ok(())
+}
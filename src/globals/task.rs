@@ -1,12 +1,47 @@
-use std::time::Duration;
+//! The task library, following Roblox's scheduler:
+//!
+//! - `task.spawn` resumes the thread immediately.
+//! - `task.defer` resumes it at the end of the current resumption cycle,
+//!   after the thread that deferred it yields or finishes. Threads deferred
+//!   by deferred threads run in the same cycle, up to 80 levels deep.
+//! - `task.delay` and `task.wait` resume the thread once the duration has
+//!   passed. Without a duration, or with zero, `task.wait` yields for one
+//!   scheduler step, after everything already queued.
+//! - `task.wait` returns the time actually waited, in seconds.
+//! - `task.cancel` closes a thread that hasn't finished, dropping whatever
+//!   it was waiting on: timers, fs operations and requires.
+//! - `task.interval` calls a function repeatedly until its handle is
+//!   cancelled.
+//! - `task.unref` lets the process exit while a thread is still waiting,
+//!   like Node's `unref` on timers, and `task.ref` undoes it. Both apply to
+//!   what the thread is waiting on when called, so the handle of a delay or
+//!   interval stays unreferenced for as long as it's pending.
+
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
 
 use crate::{library, luau, runtime};
 
+/// Reads a duration in seconds, raising an error for negative, NaN and out
+/// of range values.
+fn arg_duration(ctx: &luau::Context, idx: u32) -> Duration {
+    match Duration::try_from_secs_f64(ctx.arg_number(idx)) {
+        Ok(duration) => duration,
+        Err(_) => ctx.push_error(format!(
+            "bad argument #{idx} to function (invalid duration)"
+        )),
+    }
+}
+
 pub struct Task;
-library!(Task, spawn, defer, delay, wait);
+library!(
+    Task, spawn, defer, delay, wait, cancel, interval, r#ref, unref
+);
 
 impl Task {
-    extern "C-unwind" fn spawn(ctx: luau::Context) -> luau::FnReturn {
+    pub(crate) extern "C-unwind" fn spawn(ctx: luau::Context) -> luau::FnReturn {
         let thread = match ctx.type_of(1) {
             luau::Type::Thread => ctx.to_thread(1).unwrap(),
             luau::Type::Function => {
@@ -29,7 +64,7 @@ impl Task {
         ctx.ret_with(1)
     }
 
-    extern "C-unwind" fn defer(ctx: luau::Context) -> luau::FnReturn {
+    pub(crate) extern "C-unwind" fn defer(ctx: luau::Context) -> luau::FnReturn {
         let thread = match ctx.type_of(1) {
             luau::Type::Thread => ctx.to_thread(1).unwrap(),
             luau::Type::Function => {
@@ -49,19 +84,14 @@ impl Task {
         let nargs = ctx.get_top() - 1;
         ctx.xmove(&thread, nargs);
 
-        {
-            let main = ctx.main();
-            ctx.spawner().defer(async move {
-                main.spawn(&r.to_thread(), nargs);
-
-                drop(r);
-            });
+        if let Err(e) = ctx.main().defer(r, nargs) {
+            ctx.push_error(e);
         }
 
         ctx.ret_with(1)
     }
 
-    extern "C-unwind" fn delay(ctx: luau::Context) -> luau::FnReturn {
+    pub(crate) extern "C-unwind" fn delay(ctx: luau::Context) -> luau::FnReturn {
         let thread = match ctx.type_of(1) {
             luau::Type::Thread => ctx.to_thread(1).unwrap(),
             luau::Type::Function => {
@@ -78,44 +108,115 @@ impl Task {
 
         let r = ctx.to_ref(1);
 
-        let delay = Duration::from_secs_f64(ctx.arg_number(2));
+        let delay = arg_duration(&ctx, 2);
 
         let nargs = ctx.get_top() - 2;
         ctx.xmove(&thread, nargs);
 
-        {
-            let main = ctx.main();
-            ctx.spawner().spawn(async move {
-                runtime::time::sleep(delay).await;
-                main.spawn(&r.to_thread(), nargs);
-
-                drop(r);
+        let main = ctx.main();
+        ctx.main()
+            .park(r, runtime::time::sleep(delay), move |thread, ()| {
+                main.spawn(thread, nargs)
             });
-        }
 
         ctx.pop(1);
         ctx.ret_with(1)
     }
 
-    extern "C-unwind" fn wait(ctx: luau::Context) -> luau::FnReturn {
-        let delay = Duration::from_secs_f64(ctx.arg_number(1));
+    pub(crate) extern "C-unwind" fn wait(ctx: luau::Context) -> luau::FnReturn {
+        let delay = match ctx.arg_number_opt(1) {
+            Some(seconds) if seconds > 0.0 => Some(arg_duration(&ctx, 1)),
+            _ => None,
+        };
+
+        let start = Instant::now();
+
+        // parked futures are first polled after everything already queued, so
+        // no delay still waits a cycle
+        ctx.yield_async(async move {
+            if let Some(delay) = delay {
+                runtime::time::sleep(delay).await;
+            }
+
+            Ok::<_, Infallible>(start.elapsed().as_secs_f64())
+        })
+    }
+
+    pub(crate) extern "C-unwind" fn cancel(ctx: luau::Context) -> luau::FnReturn {
+        let thread = ctx.arg_thread(1);
+
+        if matches!(
+            thread.coro_status(),
+            luau::CoroStatus::Run | luau::CoroStatus::Normal
+        ) {
+            ctx.push_error("cannot cancel a running thread");
+        }
 
-        let thread = ctx.thread();
+        ctx.main().cancel(&thread);
+        super::require::cancel(&ctx, &thread);
+        thread.reset();
+
+        ctx.ret()
+    }
 
-        ctx.push_thread(&thread);
-        let r = ctx.to_ref(-1);
+    /// Calls the function every `seconds` with the given arguments, each time
+    /// on a new thread, returning a thread to pass to `task.cancel`.
+    pub(crate) extern "C-unwind" fn interval(ctx: luau::Context) -> luau::FnReturn {
+        let period = arg_duration(&ctx, 1);
+        if period.is_zero() {
+            ctx.push_error("bad argument #1 to function (interval must be positive)");
+        }
+
+        if !ctx.is_function(2) {
+            ctx.push_error("bad argument #2 to function (function expected)");
+        }
+
+        let nargs = ctx.get_top() - 2;
+
+        // the handle holds the function and its arguments
+        let handle = ctx.push_thread_new();
+        ctx.insert(1);
+        ctx.xmove(&handle, nargs + 1);
         ctx.pop(1);
 
-        {
-            let main = ctx.main();
-            ctx.spawner().spawn(async move {
-                runtime::time::sleep(delay).await;
-                main.spawn(&r.to_thread(), 0);
+        let main = ctx.main();
+        let ticks = async move {
+            // a period too long to represent never ticks
+            let mut next = Instant::now().checked_add(period);
+
+            while let Some(deadline) = next {
+                runtime::time::sleep_until(deadline).await;
+
+                let source = handle.stack();
+                let (r, thread) = main.new_thread();
+                for idx in 1..=nargs + 1 {
+                    source.xpush(&thread, idx as _);
+                }
 
+                main.spawn(&thread, nargs);
                 drop(r);
-            });
-        }
 
-        ctx.yld()
+                // ticks missed while the callback was busy are skipped
+                next = deadline
+                    .checked_add(period)
+                    .map(|next| next.max(Instant::now()));
+            }
+
+            std::future::pending().await
+        };
+
+        ctx.main().park(ctx.to_ref(1), ticks, |_, ()| {});
+
+        ctx.ret_with(1)
+    }
+
+    pub(crate) extern "C-unwind" fn r#ref(ctx: luau::Context) -> luau::FnReturn {
+        ctx.main().set_thread_referenced(&ctx.arg_thread(1), true);
+        ctx.ret()
+    }
+
+    pub(crate) extern "C-unwind" fn unref(ctx: luau::Context) -> luau::FnReturn {
+        ctx.main().set_thread_referenced(&ctx.arg_thread(1), false);
+        ctx.ret()
     }
 }
@@ -0,0 +1,195 @@
+use crate::luau;
+
+/// The structured formats values can be decoded from and encoded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" => Ok(Format::Yaml),
+            _ => Err(format!("unknown format '{s}', expected json, toml or yaml")),
+        }
+    }
+}
+
+impl Format {
+    pub fn decode(self, data: &[u8]) -> Result<serde_json::Value, String> {
+        match self {
+            Format::Json => serde_json::from_slice(data).map_err(|e| e.to_string()),
+            Format::Yaml => serde_yaml::from_slice(data).map_err(|e| e.to_string()),
+            Format::Toml => {
+                let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+                Ok(from_toml(toml::from_str(text).map_err(|e| e.to_string())?))
+            }
+        }
+    }
+
+    pub fn encode(self, value: &serde_json::Value, pretty: bool) -> Result<String, String> {
+        match (self, pretty) {
+            (Format::Json, false) => serde_json::to_string(value).map_err(|e| e.to_string()),
+            (Format::Json, true) => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+            (Format::Toml, false) => toml::to_string(value).map_err(|e| e.to_string()),
+            (Format::Toml, true) => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+            (Format::Yaml, _) => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+fn from_toml(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => s.into(),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(array) => array.into_iter().map(from_toml).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(k, v)| (k, from_toml(v)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+/// Pushes `value` as the Luau value closest to it, with `null` becoming nil.
+pub fn push(stack: &luau::Stack, value: &serde_json::Value) {
+    stack.check(3);
+
+    match value {
+        serde_json::Value::Null => stack.push_nil(),
+        serde_json::Value::Bool(b) => stack.push_boolean(*b),
+        serde_json::Value::Number(n) => stack.push_number(n.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(s) => stack.push_string(s),
+
+        serde_json::Value::Array(array) => {
+            stack.push_table_with(array.len() as _, 0);
+
+            for (i, v) in array.iter().enumerate() {
+                push(stack, v);
+                stack.table_set_raw_i(-2, i as u32 + 1);
+            }
+        }
+
+        serde_json::Value::Object(object) => {
+            stack.push_table_with(0, object.len() as _);
+
+            for (k, v) in object {
+                stack.push_string(k);
+                push(stack, v);
+                stack.table_set_raw(-3);
+            }
+        }
+    }
+}
+
+const MAX_DEPTH: usize = 128;
+
+enum Key {
+    Index(usize),
+    Name(String),
+}
+
+/// Converts the value at `idx` into one that can be encoded. Tables with keys
+/// `1..n` become arrays, any other table becomes an object.
+pub fn to_value(stack: &luau::Stack, idx: i32) -> Result<serde_json::Value, String> {
+    let idx = match idx {
+        idx if idx < 0 => stack.get_top() as i32 + idx + 1,
+        idx => idx,
+    };
+
+    to_value_at(stack, idx, 0)
+}
+
+fn to_value_at(stack: &luau::Stack, idx: i32, depth: usize) -> Result<serde_json::Value, String> {
+    match stack.type_of(idx) {
+        luau::Type::None | luau::Type::Nil => Ok(serde_json::Value::Null),
+        luau::Type::Boolean => Ok(stack.to_boolean(idx).unwrap().into()),
+
+        luau::Type::Number => {
+            let n = stack.to_number(idx).unwrap();
+
+            if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+                Ok((n as i64).into())
+            } else {
+                serde_json::Number::from_f64(n)
+                    .map(Into::into)
+                    .ok_or_else(|| format!("cannot encode {n}"))
+            }
+        }
+
+        luau::Type::String => match std::str::from_utf8(stack.to_string_slice(idx).unwrap()) {
+            Ok(s) => Ok(s.into()),
+            Err(_) => Err("cannot encode a string that is not valid utf8".into()),
+        },
+
+        luau::Type::Table => {
+            if depth >= MAX_DEPTH {
+                return Err("cannot encode a table nested this deeply, is it cyclic?".into());
+            }
+
+            stack.check(3);
+
+            let len = stack.len(idx);
+            let mut array = vec![serde_json::Value::Null; len];
+            let mut object = serde_json::Map::new();
+
+            stack.push_nil(); // key
+            while stack.next(idx) {
+                // key, value
+                let value = to_value_at(stack, stack.get_top() as i32, depth + 1);
+
+                let key = match stack.type_of(-2) {
+                    luau::Type::Number => {
+                        let n = stack.to_number(-2).unwrap();
+
+                        match n as usize {
+                            i if i as f64 == n && (1..=len).contains(&i) => Ok(Key::Index(i - 1)),
+                            _ => Ok(Key::Name(n.to_string())),
+                        }
+                    }
+
+                    luau::Type::String => std::str::from_utf8(stack.to_string_slice(-2).unwrap())
+                        .map(|s| Key::Name(s.to_owned()))
+                        .map_err(|_| "cannot encode a key that is not valid utf8".to_owned()),
+
+                    ty => Err(format!("cannot encode a table with {ty} keys")),
+                };
+
+                stack.pop(1); // key
+
+                match (key, value) {
+                    (Ok(Key::Index(i)), Ok(value)) => array[i] = value,
+                    (Ok(Key::Name(key)), Ok(value)) => drop(object.insert(key, value)),
+                    (Err(e), _) | (_, Err(e)) => {
+                        stack.pop(1); // stack is as it was
+                        return Err(e);
+                    }
+                }
+            }
+
+            if object.is_empty() && len > 0 {
+                Ok(array.into())
+            } else {
+                object.extend(
+                    array
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, v)| ((i + 1).to_string(), v)),
+                );
+
+                Ok(object.into())
+            }
+        }
+
+        ty => Err(format!("cannot encode a {ty} value")),
+    }
+}
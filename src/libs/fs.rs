@@ -1,67 +1,355 @@
-use std::path::Path;
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{library, luau, runtime};
 
+mod file;
+mod walk;
+mod watch;
+
+pub use file::File;
+pub use walk::Walker;
+pub use watch::Watcher;
+
 pub struct Fs;
-library!(Fs, read, write);
+library!(
+    Fs,
+    open,
+    read,
+    write,
+    writeAtomic,
+    metadata,
+    exists,
+    readDir,
+    mkdir,
+    remove,
+    removeDir,
+    rename,
+    copy,
+    symlink,
+    readLink,
+    setPermissions,
+    tempFile,
+    tempDir,
+    watch,
+    walk,
+    glob
+);
+
+/// Pushes `err` as a table carrying its kind and errno, which prints as its
+/// message.
+fn push_io_error(stack: &luau::Stack, path: &Path, err: &io::Error) {
+    extern "C-unwind" fn tostring(ctx: luau::Context) -> luau::FnReturn {
+        ctx.table_get_raw_field(1, c"message");
+        ctx.ret_with(1)
+    }
+
+    stack.push_table(); // err
+
+    stack.push_string(format!("{:?}", err.kind()));
+    stack.table_set_raw_field(-2, c"kind");
+
+    if let Some(errno) = err.raw_os_error() {
+        stack.push_number(errno as f64);
+        stack.table_set_raw_field(-2, c"errno");
+    }
+
+    stack.push_string(path.as_os_str().as_bytes());
+    stack.table_set_raw_field(-2, c"path");
+
+    stack.push_string(format!("{}: {err}", path.display()));
+    stack.table_set_raw_field(-2, c"message");
+
+    stack.push_table(); // err, mt
+    stack.push_function(c"Fs::error", tostring);
+    stack.table_set_raw_field(-2, c"__tostring");
+    stack.set_metatable(-2); // err
+}
+
+/// An io error and the path it happened on, pushed as an error table.
+pub(crate) struct IoError(PathBuf, io::Error);
+
+impl luau::PushError for IoError {
+    fn push_error(self, stack: &luau::Stack) {
+        push_io_error(stack, &self.0, &self.1);
+    }
+}
+
+/// Attaches `path` to the error `future` fails with, for `yield_async`.
+pub(crate) async fn with_path<T>(
+    path: PathBuf,
+    future: impl Future<Output = io::Result<T>>,
+) -> Result<T, IoError> {
+    future.await.map_err(|e| IoError(path, e))
+}
+
+/// Yields until `future` completes, then resumes the calling thread with the
+/// values `push` leaves on its stack, or with the error it failed with.
+pub(crate) fn resolve<T: 'static>(
+    ctx: luau::Context,
+    path: PathBuf,
+    future: impl Future<Output = io::Result<T>> + 'static,
+    push: impl FnOnce(&luau::Stack, T) -> u32 + 'static,
+) -> luau::FnReturn {
+    ctx.yield_async(async move {
+        let value = with_path(path, future).await?;
+        Ok::<_, IoError>(luau::PushWith(move |stack: &luau::Stack| {
+            push(stack, value)
+        }))
+    })
+}
+
+/// Parks the calling thread like `resolve` without yielding, returning the
+/// id `Main::set_referenced` takes.
+pub(crate) fn park<T: 'static>(
+    ctx: &luau::Context,
+    path: PathBuf,
+    future: impl Future<Output = io::Result<T>> + 'static,
+    push: impl FnOnce(&luau::Stack, T) -> u32 + 'static,
+) -> u64 {
+    ctx.park_async(async move {
+        let value = with_path(path, future).await?;
+        Ok::<_, IoError>(luau::PushWith(move |stack: &luau::Stack| {
+            push(stack, value)
+        }))
+    })
+}
+
+fn arg_contents(ctx: &luau::Context, idx: u32) -> Vec<u8> {
+    match ctx.type_of(idx as _) {
+        luau::Type::String => ctx.to_string_slice(idx as _).unwrap().to_vec(),
+        luau::Type::Buffer => ctx.to_buffer(idx as _).unwrap().to_vec(),
 
+        _ => ctx.push_error(format!(
+            "bad argument #{idx} to function (string or buffer expected)"
+        )),
+    }
+}
+
+fn push_time(stack: &luau::Stack, time: io::Result<SystemTime>, key: &std::ffi::CStr) {
+    if let Ok(since) = time.and_then(|t| t.duration_since(UNIX_EPOCH).map_err(io::Error::other)) {
+        stack.push_number(since.as_secs_f64());
+        stack.table_set_raw_field(-2, key);
+    }
+}
+
+#[allow(non_snake_case)]
 impl Fs {
-    extern "C-unwind" fn read(ctx: luau::Context) -> luau::FnReturn {
-        let path = Path::new(ctx.arg_string_str(1)).to_owned();
-        let main = ctx.main();
-        let r = ctx.thread().to_ref();
-        ctx.spawner().spawn(async move {
-            let result = runtime::fs::read(path).await;
-            let thread = r.to_thread();
-
-            match result {
-                Ok(data) => {
-                    thread.stack().push_string(data);
-                    main.spawn(&thread, 1);
-                }
-
-                Err(e) => {
-                    thread.stack().push_string(e.to_string());
-                    main.spawn_error(&thread);
-                }
-            }
+    extern "C-unwind" fn open(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let mode = ctx.arg_string_opt_str(2).unwrap_or("r").to_owned();
+        let future = file::open(path.clone(), mode);
 
-            drop(r);
-        });
+        resolve(ctx, path, future, |stack, file| {
+            stack.push_userdata(file);
+            1
+        })
+    }
 
-        ctx.yld()
+    pub(crate) extern "C-unwind" fn read(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::read(path.clone());
+
+        ctx.yield_async(with_path(path, future))
     }
 
-    extern "C-unwind" fn write(ctx: luau::Context) -> luau::FnReturn {
-        let path = Path::new(ctx.arg_string_str(1)).to_owned();
-        let data = match ctx.type_of(2) {
-            luau::Type::String => ctx.to_string_slice(2).unwrap().to_vec(),
-            luau::Type::Buffer => ctx.to_buffer(2).unwrap().to_vec(),
+    pub(crate) extern "C-unwind" fn write(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::write(path.clone(), arg_contents(&ctx, 2));
+
+        ctx.yield_async(with_path(path, future))
+    }
 
-            _ => ctx.push_error("bad argument #2 to 'write' (string or buffer expected)"),
-        };
+    extern "C-unwind" fn writeAtomic(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::write_atomic(path.clone(), arg_contents(&ctx, 2));
 
-        let main = ctx.main();
-        let r = ctx.thread().to_ref();
-        ctx.spawner().spawn(async move {
-            let result = runtime::fs::write(path, data).await;
-            let thread = r.to_thread();
+        ctx.yield_async(with_path(path, future))
+    }
+
+    extern "C-unwind" fn metadata(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::metadata(path.clone());
+
+        resolve(ctx, path, future, |stack, metadata| {
+            let kind = match metadata.file_type() {
+                t if t.is_file() => "file",
+                t if t.is_dir() => "dir",
+                t if t.is_symlink() => "symlink",
+                _ => "other",
+            };
+
+            stack.push_table(); // metadata
+
+            stack.push_string(kind);
+            stack.table_set_raw_field(-2, c"kind");
+
+            stack.push_number(metadata.len() as f64);
+            stack.table_set_raw_field(-2, c"size");
+
+            stack.push_number((metadata.permissions().mode() & 0o7777) as f64);
+            stack.table_set_raw_field(-2, c"permissions");
+
+            stack.push_boolean(metadata.permissions().readonly());
+            stack.table_set_raw_field(-2, c"readonly");
+
+            push_time(stack, metadata.created(), c"createdAt");
+            push_time(stack, metadata.modified(), c"modifiedAt");
+            push_time(stack, metadata.accessed(), c"accessedAt");
+
+            1
+        })
+    }
 
-            match result {
-                Ok(_) => {
-                    thread.stack().push_nil();
-                    main.spawn(&thread, 1);
-                }
+    extern "C-unwind" fn exists(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::exists(path.clone());
 
-                Err(e) => {
-                    thread.stack().push_string(e.to_string());
-                    main.spawn_error(&thread);
-                }
+        ctx.yield_async(with_path(path, future))
+    }
+
+    pub(crate) extern "C-unwind" fn readDir(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::read_dir(path.clone());
+
+        resolve(ctx, path, future, |stack, names| {
+            stack.push_table_with(names.len() as _, 0);
+
+            for (i, name) in names.iter().enumerate() {
+                stack.push_string(name.as_bytes());
+                stack.table_set_raw_i(-2, i as u32 + 1);
             }
 
-            drop(r);
+            1
+        })
+    }
+
+    pub(crate) extern "C-unwind" fn mkdir(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let recursive = ctx.arg_boolean_opt(2).unwrap_or(false);
+        let future = runtime::fs::create_dir(path.clone(), recursive);
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    pub(crate) extern "C-unwind" fn remove(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::remove_file(path.clone());
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    pub(crate) extern "C-unwind" fn removeDir(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let recursive = ctx.arg_boolean_opt(2).unwrap_or(false);
+        let future = runtime::fs::remove_dir(path.clone(), recursive);
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    extern "C-unwind" fn rename(ctx: luau::Context) -> luau::FnReturn {
+        let (from, to) = (ctx.arg_path(1), ctx.arg_path(2));
+        let future = runtime::fs::rename(from.clone(), to);
+
+        ctx.yield_async(with_path(from, future))
+    }
+
+    extern "C-unwind" fn copy(ctx: luau::Context) -> luau::FnReturn {
+        let (from, to) = (ctx.arg_path(1), ctx.arg_path(2));
+        let future = runtime::fs::copy(from.clone(), to);
+
+        ctx.yield_async(with_path(from, future))
+    }
+
+    extern "C-unwind" fn symlink(ctx: luau::Context) -> luau::FnReturn {
+        let (target, link) = (ctx.arg_path(1), ctx.arg_path(2));
+        let future = runtime::fs::symlink(target, link.clone());
+
+        ctx.yield_async(with_path(link, future))
+    }
+
+    extern "C-unwind" fn readLink(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = runtime::fs::read_link(path.clone());
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    extern "C-unwind" fn setPermissions(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let mode = ctx.arg_number(2);
+
+        if mode.fract() != 0.0 || !(0.0..=0o7777 as f64).contains(&mode) {
+            ctx.push_error(format!(
+                "bad argument #2 to function (permissions must be an integer between 0 and 0o7777, got {mode})"
+            ))
+        }
+
+        let mode = mode as u32;
+        let future = runtime::fs::set_permissions(path.clone(), mode);
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    extern "C-unwind" fn tempFile(ctx: luau::Context) -> luau::FnReturn {
+        let prefix = ctx
+            .arg_string_opt_slice(1)
+            .map(|prefix| OsStr::from_bytes(prefix).to_owned());
+        let future = runtime::fs::temp_file(prefix);
+
+        ctx.yield_async(with_path(std::env::temp_dir(), future))
+    }
+
+    extern "C-unwind" fn tempDir(ctx: luau::Context) -> luau::FnReturn {
+        let prefix = ctx
+            .arg_string_opt_slice(1)
+            .map(|prefix| OsStr::from_bytes(prefix).to_owned());
+        let future = runtime::fs::temp_dir(prefix);
+
+        ctx.yield_async(with_path(std::env::temp_dir(), future))
+    }
+
+    /// `fs.watch(path, { recursive = true })` returns a `Watcher` whose `next`
+    /// yields create, modify, remove and rename events.
+    extern "C-unwind" fn watch(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let recursive = ctx.arg_table_opt(2).is_some_and(|_| {
+            ctx.table_get_raw_field(2, c"recursive");
+            let recursive = ctx.to_boolean(-1).unwrap_or(false);
+            ctx.pop(1);
+            recursive
         });
+        let future = runtime::watch::watch(path.clone(), recursive);
+
+        resolve(ctx, path.clone(), future, move |stack, inner| {
+            watch::push(stack, path, inner)
+        })
+    }
+
+    /// `fs.walk(dir, options)` returns a `Walker` over everything below `dir`.
+    extern "C-unwind" fn walk(ctx: luau::Context) -> luau::FnReturn {
+        let root = ctx.arg_path(1);
+        let options = walk::arg_options(&ctx, 2);
+
+        walk::push(&ctx, root.clone(), runtime::walk::walk(root, options));
+        ctx.ret_with(1)
+    }
+
+    /// `fs.glob(pattern, options)` returns a `Walker` over the matching paths.
+    extern "C-unwind" fn glob(ctx: luau::Context) -> luau::FnReturn {
+        let pattern = ctx.arg_string_str(1).to_owned();
+        let options = walk::arg_options(&ctx, 2);
+
+        match runtime::walk::glob(&pattern, options) {
+            Ok(inner) => walk::push(&ctx, PathBuf::from(pattern), inner),
+            Err(e) => ctx.push_error(format!("invalid glob '{pattern}': {e}")),
+        }
 
-        ctx.yld()
+        ctx.ret_with(1)
     }
 }
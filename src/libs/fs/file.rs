@@ -0,0 +1,306 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{resolve, with_path};
+use crate::{
+    luau,
+    runtime::{time::sleep, util::unblock},
+    userdata,
+};
+
+/// The bounds of the backoff between attempts to take a contended lock.
+const LOCK_RETRY_MIN: Duration = Duration::from_millis(1);
+const LOCK_RETRY_MAX: Duration = Duration::from_millis(50);
+
+struct Inner {
+    file: Option<std::fs::File>,
+
+    /// Bytes read past the end of the last line returned by `readLine`.
+    buffer: Vec<u8>,
+}
+
+impl Inner {
+    fn file(&mut self) -> io::Result<&mut std::fs::File> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("file is closed"))
+    }
+
+    /// Moves the descriptor back over any read-ahead bytes, so it points
+    /// where the script thinks it does.
+    fn unread(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let ahead = self.buffer.len() as i64;
+            self.file()?.seek(SeekFrom::Current(-ahead))?;
+            self.buffer.clear();
+        }
+
+        Ok(())
+    }
+}
+
+/// A handle returned by `fs.open`. The descriptor is closed by `close` or
+/// when the handle is garbage collected, whichever happens first.
+pub struct File {
+    path: PathBuf,
+    inner: Arc<Mutex<Inner>>,
+}
+
+userdata!(
+    File, read, readLine, write, seek, flush, truncate, lock, unlock, close
+);
+
+/// Opens `path` with a C `fopen` style mode.
+pub async fn open(path: PathBuf, mode: String) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+
+    match mode.trim_end_matches('b') {
+        "r" => options.read(true),
+        "r+" => options.read(true).write(true),
+        "w" => options.write(true).create(true).truncate(true),
+        "w+" => options.read(true).write(true).create(true).truncate(true),
+        "a" => options.append(true).create(true),
+        "a+" => options.read(true).append(true).create(true),
+        "x" => options.write(true).create_new(true),
+        "x+" => options.read(true).write(true).create_new(true),
+
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid mode '{mode}'"),
+            ));
+        }
+    };
+
+    let file = {
+        let path = path.clone();
+        unblock(move || options.open(path)).await?
+    };
+
+    Ok(File {
+        path,
+        inner: Arc::new(Mutex::new(Inner {
+            file: Some(file),
+            buffer: Vec::new(),
+        })),
+    })
+}
+
+impl File {
+    /// Runs `func` on the rayon pool with the file locked, resuming the
+    /// calling thread with what `push` leaves on its stack.
+    fn with<T: Send + 'static>(
+        ctx: luau::Context,
+        func: impl FnOnce(&mut Inner) -> io::Result<T> + Send + 'static,
+        push: impl FnOnce(&luau::Stack, T) -> u32 + 'static,
+    ) -> luau::FnReturn {
+        let file = ctx.arg_userdata::<File>(1);
+        let (path, inner) = (file.path.clone(), file.inner.clone());
+
+        let future = unblock(move || func(&mut inner.lock().unwrap()));
+        resolve(ctx, path, future, push)
+    }
+
+    extern "C-unwind" fn read(ctx: luau::Context) -> luau::FnReturn {
+        let count = ctx.arg_number_opt(2).map(|n| n as usize);
+
+        Self::with(
+            ctx,
+            move |inner| {
+                let mut data = std::mem::take(&mut inner.buffer);
+
+                match count {
+                    Some(count) if data.len() >= count => {
+                        inner.buffer = data.split_off(count);
+                    }
+
+                    Some(count) => {
+                        let wanted = (count - data.len()) as u64;
+                        inner.file()?.take(wanted).read_to_end(&mut data)?;
+                    }
+
+                    None => {
+                        inner.file()?.read_to_end(&mut data)?;
+                    }
+                }
+
+                Ok((!data.is_empty() || count == Some(0)).then_some(data))
+            },
+            push_bytes,
+        )
+    }
+
+    #[allow(non_snake_case)]
+    extern "C-unwind" fn readLine(ctx: luau::Context) -> luau::FnReturn {
+        Self::with(
+            ctx,
+            |inner| {
+                let mut chunk = [0; 8192];
+
+                loop {
+                    if let Some(end) = inner.buffer.iter().position(|&b| b == b'\n') {
+                        let rest = inner.buffer.split_off(end + 1);
+                        let mut line = std::mem::replace(&mut inner.buffer, rest);
+
+                        line.pop();
+                        if line.last() == Some(&b'\r') {
+                            line.pop();
+                        }
+
+                        return Ok(Some(line));
+                    }
+
+                    match inner.file()?.read(&mut chunk)? {
+                        0 if inner.buffer.is_empty() => return Ok(None),
+                        0 => return Ok(Some(std::mem::take(&mut inner.buffer))),
+                        n => inner.buffer.extend_from_slice(&chunk[..n]),
+                    }
+                }
+            },
+            push_bytes,
+        )
+    }
+
+    extern "C-unwind" fn write(ctx: luau::Context) -> luau::FnReturn {
+        let data = super::arg_contents(&ctx, 2);
+
+        Self::with(
+            ctx,
+            move |inner| {
+                inner.unread()?;
+                inner.file()?.write_all(&data)
+            },
+            |_, _| 0,
+        )
+    }
+
+    extern "C-unwind" fn seek(ctx: luau::Context) -> luau::FnReturn {
+        let offset = ctx.arg_number_opt(3).unwrap_or(0.0) as i64;
+        let pos = match ctx.arg_string_opt_str(2).unwrap_or("cur") {
+            "set" => SeekFrom::Start(offset.max(0) as u64),
+            "cur" => SeekFrom::Current(offset),
+            "end" => SeekFrom::End(offset),
+            whence => ctx.push_error(format!(
+                "bad argument #2 to 'seek' (invalid option '{whence}')"
+            )),
+        };
+
+        Self::with(
+            ctx,
+            move |inner| {
+                inner.unread()?;
+                inner.file()?.seek(pos)
+            },
+            |stack, pos| {
+                stack.push_number(pos as f64);
+                1
+            },
+        )
+    }
+
+    /// Flushes written data through to the disk.
+    extern "C-unwind" fn flush(ctx: luau::Context) -> luau::FnReturn {
+        Self::with(ctx, |inner| inner.file()?.sync_data(), |_, _| 0)
+    }
+
+    /// Cuts the file off at `size`, or at the current position.
+    extern "C-unwind" fn truncate(ctx: luau::Context) -> luau::FnReturn {
+        let size = ctx.arg_number_opt(2).map(|n| n as u64);
+
+        Self::with(
+            ctx,
+            move |inner| {
+                inner.unread()?;
+
+                let file = inner.file()?;
+                let size = match size {
+                    Some(size) => size,
+                    None => file.stream_position()?,
+                };
+
+                file.set_len(size)
+            },
+            |_, _| 0,
+        )
+    }
+
+    /// Takes an advisory lock, shared if `false` is passed, waiting until any
+    /// conflicting lock is released.
+    extern "C-unwind" fn lock(ctx: luau::Context) -> luau::FnReturn {
+        let exclusive = ctx.arg_boolean_opt(2).unwrap_or(true);
+        let operation = if exclusive {
+            libc::LOCK_EX
+        } else {
+            libc::LOCK_SH
+        };
+
+        let file = ctx.arg_userdata::<File>(1);
+        let (path, inner) = (file.path.clone(), file.inner.clone());
+
+        // a blocking flock would hold a pool thread for as long as the lock is
+        // contended, so poll for it instead
+        let future = async move {
+            let mut delay = LOCK_RETRY_MIN;
+
+            loop {
+                let inner = inner.clone();
+                let attempt =
+                    unblock(move || flock(&mut inner.lock().unwrap(), operation | libc::LOCK_NB));
+
+                match attempt.await {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        sleep(delay).await;
+                        delay = (delay * 2).min(LOCK_RETRY_MAX);
+                    }
+
+                    result => return result,
+                }
+            }
+        };
+
+        ctx.yield_async(with_path(path, future))
+    }
+
+    extern "C-unwind" fn unlock(ctx: luau::Context) -> luau::FnReturn {
+        Self::with(ctx, |inner| flock(inner, libc::LOCK_UN), |_, _| 0)
+    }
+
+    extern "C-unwind" fn close(ctx: luau::Context) -> luau::FnReturn {
+        Self::with(
+            ctx,
+            |inner| {
+                inner.buffer.clear();
+                inner
+                    .file
+                    .take()
+                    .map(drop)
+                    .ok_or_else(|| io::Error::other("file is closed"))
+            },
+            |_, _| 0,
+        )
+    }
+}
+
+fn flock(inner: &mut Inner, operation: i32) -> io::Result<()> {
+    let fd = inner.file()?.as_raw_fd();
+
+    match unsafe { libc::flock(fd, operation) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn push_bytes(stack: &luau::Stack, data: Option<Vec<u8>>) -> u32 {
+    match data {
+        Some(data) => stack.push_string(data),
+        None => stack.push_nil(),
+    }
+
+    1
+}
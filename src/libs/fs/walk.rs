@@ -0,0 +1,75 @@
+use std::{os::unix::ffi::OsStrExt, path::PathBuf};
+
+use super::resolve;
+use crate::{
+    luau,
+    runtime::walk::{Symlinks, Walk, WalkOptions},
+    userdata,
+};
+
+/// An iterator returned by `fs.walk` and `fs.glob`. The traversal runs ahead
+/// on the rayon pool and stops once the handle is garbage collected.
+pub struct Walker {
+    root: PathBuf,
+    inner: Walk,
+}
+
+userdata!(Walker, next);
+
+pub fn push(stack: &luau::Stack, root: PathBuf, inner: Walk) {
+    stack.push_userdata(Walker { root, inner });
+}
+
+/// Reads `{ depth, symlinks, ignore, hidden }` from the table at `idx`, if
+/// there is one.
+pub fn arg_options(ctx: &luau::Context, idx: u32) -> WalkOptions {
+    let mut options = WalkOptions::default();
+
+    if ctx.arg_table_opt(idx).is_none() {
+        return options;
+    }
+
+    ctx.table_get_raw_field(idx as _, c"depth");
+    options.max_depth = ctx.to_number(-1).map(|depth| depth.max(0.0) as usize);
+
+    ctx.table_get_raw_field(idx as _, c"symlinks");
+    options.symlinks = match ctx.to_string_str(-1) {
+        None | Some("include") => Symlinks::Include,
+        Some("follow") => Symlinks::Follow,
+        Some("skip") => Symlinks::Skip,
+        Some(policy) => ctx.push_error(format!("invalid symlink policy '{policy}'")),
+    };
+
+    ctx.table_get_raw_field(idx as _, c"ignore");
+    options.ignore = ctx.to_boolean(-1).unwrap_or(true);
+
+    ctx.table_get_raw_field(idx as _, c"hidden");
+    options.hidden = ctx.to_boolean(-1).unwrap_or(false);
+
+    ctx.pop(4);
+    options
+}
+
+impl Walker {
+    /// Yields until the next entry and returns its path and kind, or returns
+    /// nil once the traversal is over.
+    extern "C-unwind" fn next(ctx: luau::Context) -> luau::FnReturn {
+        let walker = ctx.arg_userdata::<Walker>(1);
+        let (root, next) = (walker.root.clone(), walker.inner.next());
+
+        resolve(
+            ctx,
+            root,
+            async move { next.await.transpose() },
+            |stack, entry| {
+                let Some(entry) = entry else {
+                    return 0;
+                };
+
+                stack.push_string(entry.path.as_os_str().as_bytes());
+                stack.push_string(entry.kind);
+                2
+            },
+        )
+    }
+}
@@ -0,0 +1,96 @@
+use std::{os::unix::ffi::OsStrExt, path::PathBuf};
+
+use super::park;
+use crate::{luau, runtime, userdata};
+
+/// An event source returned by `fs.watch`. Events are queued from the moment
+/// the watcher is created, and the watches are removed by `close` or when the
+/// handle is garbage collected. Threads waiting on `next` keep the process
+/// running unless the watcher is unreferenced.
+pub struct Watcher {
+    path: PathBuf,
+    inner: runtime::watch::Watcher,
+
+    referenced: bool,
+
+    /// The parked `next` calls, for `ref` and `unref` to update.
+    waiting: Vec<u64>,
+}
+
+userdata!(Watcher, next, close, r#ref, unref);
+
+pub fn push(stack: &luau::Stack, path: PathBuf, inner: runtime::watch::Watcher) -> u32 {
+    stack.push_userdata(Watcher {
+        path,
+        inner,
+        referenced: true,
+        waiting: Vec::new(),
+    });
+    1
+}
+
+impl Watcher {
+    /// Yields until the next event and returns it as a table, or returns nil
+    /// once the watcher is closed.
+    extern "C-unwind" fn next(ctx: luau::Context) -> luau::FnReturn {
+        let watcher = ctx.arg_userdata::<Watcher>(1);
+        let (path, next) = (watcher.path.clone(), watcher.inner.next());
+
+        let id = park(&ctx, path, async move { Ok(next.await) }, |stack, event| {
+            let Some(event) = event else {
+                return 0;
+            };
+
+            stack.push_table();
+
+            stack.push_string(event.kind.as_str());
+            stack.table_set_raw_field(-2, c"kind");
+
+            stack.push_string(event.path.as_os_str().as_bytes());
+            stack.table_set_raw_field(-2, c"path");
+
+            if let Some(from) = event.from {
+                stack.push_string(from.as_os_str().as_bytes());
+                stack.table_set_raw_field(-2, c"from");
+            }
+
+            1
+        });
+
+        let main = ctx.main();
+
+        watcher.waiting.retain(|&id| main.is_parked(id));
+        watcher.waiting.push(id);
+
+        if !watcher.referenced {
+            main.set_referenced(id, false);
+        }
+
+        ctx.yld()
+    }
+
+    extern "C-unwind" fn close(ctx: luau::Context) -> luau::FnReturn {
+        ctx.arg_userdata::<Watcher>(1).inner.close();
+        ctx.ret()
+    }
+
+    /// Makes waiting on the watcher keep the process running again.
+    extern "C-unwind" fn r#ref(ctx: luau::Context) -> luau::FnReturn {
+        ctx.arg_userdata::<Watcher>(1).set_referenced(&ctx, true);
+        ctx.ret()
+    }
+
+    /// Lets the process exit while threads are waiting on the watcher.
+    extern "C-unwind" fn unref(ctx: luau::Context) -> luau::FnReturn {
+        ctx.arg_userdata::<Watcher>(1).set_referenced(&ctx, false);
+        ctx.ret()
+    }
+
+    fn set_referenced(&mut self, ctx: &luau::Context, referenced: bool) {
+        let main = ctx.main();
+
+        self.referenced = referenced;
+        self.waiting
+            .retain(|&id| main.set_referenced(id, referenced));
+    }
+}
@@ -0,0 +1,116 @@
+use std::{io, path::PathBuf};
+
+use crate::{
+    library, libs,
+    luau::{self, Library},
+    runtime,
+};
+
+pub struct Fs;
+library!(
+    Fs, readFile, writeFile, readDir, writeDir, removeFile, removeDir, isFile, isDir, copy
+);
+
+/// Pushes the library along with `move`, which cannot go through `library!`
+/// as it is a Rust keyword.
+pub fn push(stack: luau::Stack) {
+    let thread = stack.thread();
+    Fs::push(stack);
+
+    let stack = thread.stack();
+    stack.push_function(c"Fs::move", Fs::move_);
+    stack.table_set_raw_field(-2, c"move");
+}
+
+async fn is_kind(path: PathBuf, dir: bool) -> io::Result<bool> {
+    match runtime::fs::metadata(path).await {
+        Ok(metadata) => Ok(metadata.is_dir() == dir && (dir || metadata.is_file())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fails the way Lune does when `to` exists and overwriting was not asked for.
+async fn check_overwrite(to: &PathBuf, overwrite: bool) -> io::Result<()> {
+    if !overwrite && runtime::fs::exists(to).await? {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+impl Fs {
+    extern "C-unwind" fn readFile(ctx: luau::Context) -> luau::FnReturn {
+        libs::fs::Fs::read(ctx)
+    }
+
+    extern "C-unwind" fn writeFile(ctx: luau::Context) -> luau::FnReturn {
+        libs::fs::Fs::write(ctx)
+    }
+
+    extern "C-unwind" fn readDir(ctx: luau::Context) -> luau::FnReturn {
+        libs::fs::Fs::readDir(ctx)
+    }
+
+    extern "C-unwind" fn writeDir(ctx: luau::Context) -> luau::FnReturn {
+        ctx.set_top(1);
+        ctx.push_boolean(true);
+        libs::fs::Fs::mkdir(ctx)
+    }
+
+    extern "C-unwind" fn removeFile(ctx: luau::Context) -> luau::FnReturn {
+        libs::fs::Fs::remove(ctx)
+    }
+
+    extern "C-unwind" fn removeDir(ctx: luau::Context) -> luau::FnReturn {
+        ctx.set_top(1);
+        ctx.push_boolean(true);
+        libs::fs::Fs::removeDir(ctx)
+    }
+
+    extern "C-unwind" fn isFile(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = is_kind(path.clone(), false);
+
+        ctx.yield_async(libs::fs::with_path(path, future))
+    }
+
+    extern "C-unwind" fn isDir(ctx: luau::Context) -> luau::FnReturn {
+        let path = ctx.arg_path(1);
+        let future = is_kind(path.clone(), true);
+
+        ctx.yield_async(libs::fs::with_path(path, future))
+    }
+
+    extern "C-unwind" fn move_(ctx: luau::Context) -> luau::FnReturn {
+        let (from, to) = (ctx.arg_path(1), ctx.arg_path(2));
+        let overwrite = ctx.arg_boolean_opt(3).unwrap_or(false);
+
+        let future = async move {
+            check_overwrite(&to, overwrite).await?;
+            runtime::fs::rename(from, to).await
+        };
+
+        let path = ctx.arg_path(2);
+        ctx.yield_async(libs::fs::with_path(path, future))
+    }
+
+    extern "C-unwind" fn copy(ctx: luau::Context) -> luau::FnReturn {
+        let (from, to) = (ctx.arg_path(1), ctx.arg_path(2));
+        let overwrite = ctx.arg_boolean_opt(3).unwrap_or(false);
+
+        let future = async move {
+            check_overwrite(&to, overwrite).await?;
+
+            if overwrite && runtime::fs::metadata(&to).await.is_ok_and(|m| m.is_dir()) {
+                runtime::fs::remove_dir(&to, true).await?;
+            }
+
+            runtime::fs::copy(from, to).await
+        };
+
+        let path = ctx.arg_path(2);
+        ctx.yield_async(libs::fs::with_path(path, future))
+    }
+}
@@ -0,0 +1,138 @@
+use std::ffi::OsString;
+
+use crate::luau::{self, Library};
+
+mod fs;
+mod net;
+mod process;
+mod serde;
+mod stdio;
+mod task;
+
+/// What bre implements of one of Lune's standard libraries.
+pub struct Coverage {
+    pub name: &'static str,
+    pub supported: &'static [&'static str],
+    pub unsupported: &'static [&'static str],
+}
+
+pub const COVERAGE: &[Coverage] = &[
+    Coverage {
+        name: "fs",
+        supported: &[
+            "readFile",
+            "writeFile",
+            "readDir",
+            "writeDir",
+            "removeFile",
+            "removeDir",
+            "isFile",
+            "isDir",
+            "move",
+            "copy",
+        ],
+        unsupported: &["metadata"],
+    },
+    Coverage {
+        name: "net",
+        supported: &["jsonEncode", "jsonDecode", "urlEncode", "urlDecode"],
+        unsupported: &["request", "serve", "socket"],
+    },
+    Coverage {
+        name: "process",
+        supported: &["os", "arch", "args", "cwd", "env", "exit"],
+        unsupported: &["spawn", "exec", "create"],
+    },
+    Coverage {
+        name: "serde",
+        supported: &["encode", "decode"],
+        unsupported: &["compress", "decompress", "hash", "hmac"],
+    },
+    Coverage {
+        name: "stdio",
+        supported: &["write", "ewrite", "color", "style"],
+        unsupported: &["format", "prompt", "readLine", "readToEnd"],
+    },
+    Coverage {
+        name: "task",
+        supported: &["spawn", "defer", "delay", "wait", "cancel"],
+        unsupported: &[],
+    },
+];
+
+/// Prints which Lune APIs scripts can rely on and which are still missing.
+pub fn report() {
+    for coverage in COVERAGE {
+        println!("@lune/{}", coverage.name);
+        println!("  supported:   {}", coverage.supported.join(", "));
+        println!("  unsupported: {}", coverage.unsupported.join(", "));
+    }
+}
+
+/// Serves bre implementations of Lune's libraries under `@lune`, so scripts
+/// written for Lune can be migrated incrementally.
+pub struct Lune {
+    pub args: Vec<OsString>,
+}
+
+impl luau::ModuleProvider for Lune {
+    fn is_module(&self, path: &str) -> bool {
+        COVERAGE.iter().any(|coverage| coverage.name == path)
+    }
+
+    fn load(&self, path: &str) -> Result<luau::Module, String> {
+        let push: Box<dyn FnOnce(luau::Stack)> = match path {
+            "fs" => Box::new(fs::push),
+            "net" => Box::new(net::Net::push),
+            "process" => {
+                let args = self.args.clone();
+                Box::new(move |stack| process::push(stack, &args))
+            }
+            "serde" => Box::new(serde::Serde::push),
+            "stdio" => Box::new(stdio::Stdio::push),
+            "task" => Box::new(task::Task::push),
+            _ => return Err(format!("no library '@lune/{path}'")),
+        };
+
+        let name = path.to_owned();
+
+        Ok(luau::Module::Value(Box::new(move |stack| {
+            let thread = stack.thread();
+            push(stack);
+            guard(&thread.stack(), &name);
+        })))
+    }
+}
+
+/// Makes indexing an API bre does not implement yet an error rather than nil.
+fn guard(stack: &luau::Stack, name: &str) {
+    extern "C-unwind" fn index(ctx: luau::Context) -> luau::FnReturn {
+        // tbl, key
+
+        ctx.push_metatable(1); // tbl, key, mt
+        ctx.table_get_raw_field(-1, c"__module"); // tbl, key, mt, name
+
+        let name = ctx.to_string_str(-1).unwrap_or_default();
+        let key = ctx.to_string_str(2).unwrap_or_default();
+
+        let unsupported = COVERAGE
+            .iter()
+            .find(|coverage| coverage.name == name)
+            .is_some_and(|coverage| coverage.unsupported.contains(&key));
+
+        if unsupported {
+            ctx.push_error(format!("@lune/{name}.{key} is not supported by bre yet"));
+        }
+
+        ctx.push_nil();
+        ctx.ret_with(1)
+    }
+
+    // module
+    stack.push_table(); // module, mt
+    stack.push_function(c"lune_unsupported", index); // module, mt, index
+    stack.table_set_raw_field(-2, c"__index"); // module, mt
+    stack.push_string(name); // module, mt, name
+    stack.table_set_raw_field(-2, c"__module"); // module, mt
+    stack.set_metatable(-2); // module
+}
@@ -0,0 +1,74 @@
+use crate::{library, libs::codec, luau};
+
+pub struct Net;
+library!(Net, jsonEncode, jsonDecode, urlEncode, urlDecode);
+
+#[allow(non_snake_case)]
+impl Net {
+    extern "C-unwind" fn jsonEncode(ctx: luau::Context) -> luau::FnReturn {
+        let pretty = ctx.arg_boolean_opt(2).unwrap_or(false);
+
+        let encoded = codec::to_value(&ctx, 1)
+            .and_then(|value| codec::Format::Json.encode(&value, pretty))
+            .unwrap_or_else(|e| ctx.push_error(e));
+
+        ctx.push_string(encoded);
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn jsonDecode(ctx: luau::Context) -> luau::FnReturn {
+        let value = codec::Format::Json
+            .decode(ctx.arg_string_slice(1))
+            .unwrap_or_else(|e| ctx.push_error(e));
+
+        codec::push(&ctx, &value);
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn urlEncode(ctx: luau::Context) -> luau::FnReturn {
+        let mut encoded = Vec::new();
+
+        for &b in ctx.arg_string_slice(1) {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    encoded.push(b)
+                }
+
+                _ => encoded.extend(format!("%{b:02X}").bytes()),
+            }
+        }
+
+        ctx.push_string(encoded);
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn urlDecode(ctx: luau::Context) -> luau::FnReturn {
+        let input = ctx.arg_string_slice(1);
+        let mut decoded = Vec::with_capacity(input.len());
+        let mut i = 0;
+
+        while i < input.len() {
+            let hex = input
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+            match (input[i], hex) {
+                (b'%', Some(b)) => {
+                    decoded.push(b);
+                    i += 3;
+                }
+
+                (b'%', None) => ctx.push_error("invalid percent-encoding"),
+
+                (b, _) => {
+                    decoded.push(b);
+                    i += 1;
+                }
+            }
+        }
+
+        ctx.push_string(decoded);
+        ctx.ret_with(1)
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    ffi::OsString,
+    io::Write,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+};
+
+use crate::luau;
+
+/// Pushes the process library. Unlike Lune's, `env` is a snapshot taken when
+/// the module is first required.
+pub fn push(stack: luau::Stack, args: &[OsString]) {
+    extern "C-unwind" fn exit(ctx: luau::Context) -> luau::FnReturn {
+        let code = ctx.arg_number_opt(1).unwrap_or(0.0);
+
+        let _ = std::io::stdout().flush();
+        std::process::exit(code as i32)
+    }
+
+    /// Keeps the process running once nothing is left to do, for servers
+    /// waiting on outside events. `process.keepAlive(false)` lets it exit.
+    extern "C-unwind" fn keep_alive(ctx: luau::Context) -> luau::FnReturn {
+        let keep_alive = ctx.arg_boolean_opt(1).unwrap_or(true);
+
+        ctx.main().keep_alive(keep_alive);
+        ctx.ret()
+    }
+
+    stack.push_table(); // process
+
+    stack.push_string(std::env::consts::OS);
+    stack.table_set_raw_field(-2, c"os");
+
+    stack.push_string(std::env::consts::ARCH);
+    stack.table_set_raw_field(-2, c"arch");
+
+    stack.push_table_with(args.len() as _, 0); // process, args
+    for (i, arg) in args.iter().enumerate() {
+        stack.push_string(arg.as_bytes());
+        stack.table_set_raw_i(-2, i as u32 + 1);
+    }
+    stack.table_set_raw_field(-2, c"args"); // process
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    stack.push_string(cwd.into_os_string().into_vec());
+    stack.table_set_raw_field(-2, c"cwd");
+
+    stack.push_table(); // process, env
+    for (key, value) in std::env::vars_os() {
+        stack.push_string(key.as_bytes());
+        stack.push_string(value.as_bytes());
+        stack.table_set_raw(-3);
+    }
+    stack.table_set_raw_field(-2, c"env"); // process
+
+    stack.push_function(c"Process::exit", exit);
+    stack.table_set_raw_field(-2, c"exit");
+
+    stack.push_function(c"Process::keepAlive", keep_alive);
+    stack.table_set_raw_field(-2, c"keepAlive");
+}
@@ -0,0 +1,37 @@
+use crate::{library, libs::codec, luau};
+
+pub struct Serde;
+library!(Serde, encode, decode);
+
+impl Serde {
+    extern "C-unwind" fn encode(ctx: luau::Context) -> luau::FnReturn {
+        let format = format(&ctx, 1);
+        let pretty = ctx.arg_boolean_opt(3).unwrap_or(false);
+
+        let encoded = codec::to_value(&ctx, 2)
+            .and_then(|value| format.encode(&value, pretty))
+            .unwrap_or_else(|e| ctx.push_error(e));
+
+        ctx.push_string(encoded);
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn decode(ctx: luau::Context) -> luau::FnReturn {
+        let format = format(&ctx, 1);
+
+        let value = match ctx.type_of(2) {
+            luau::Type::Buffer => format.decode(ctx.to_buffer(2).unwrap()),
+            _ => format.decode(ctx.arg_string_slice(2)),
+        }
+        .unwrap_or_else(|e| ctx.push_error(e));
+
+        codec::push(&ctx, &value);
+        ctx.ret_with(1)
+    }
+}
+
+fn format(ctx: &luau::Context, idx: u32) -> codec::Format {
+    ctx.arg_string_str(idx)
+        .parse()
+        .unwrap_or_else(|e: String| ctx.push_error(e))
+}
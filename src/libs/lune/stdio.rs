@@ -0,0 +1,57 @@
+use std::io::Write;
+
+use crate::{library, luau};
+
+pub struct Stdio;
+library!(Stdio, write, ewrite, color, style);
+
+impl Stdio {
+    extern "C-unwind" fn write(ctx: luau::Context) -> luau::FnReturn {
+        let mut stdout = std::io::stdout();
+
+        if let Err(e) = stdout.write_all(ctx.arg_string_slice(1)) {
+            ctx.push_error(e.to_string());
+        }
+
+        let _ = stdout.flush();
+        ctx.ret()
+    }
+
+    extern "C-unwind" fn ewrite(ctx: luau::Context) -> luau::FnReturn {
+        if let Err(e) = std::io::stderr().write_all(ctx.arg_string_slice(1)) {
+            ctx.push_error(e.to_string());
+        }
+
+        ctx.ret()
+    }
+
+    extern "C-unwind" fn color(ctx: luau::Context) -> luau::FnReturn {
+        let code = match ctx.arg_string_str(1) {
+            "reset" => 0,
+            "black" => 30,
+            "red" => 31,
+            "green" => 32,
+            "yellow" => 33,
+            "blue" => 34,
+            "purple" => 35,
+            "cyan" => 36,
+            "white" => 37,
+            name => ctx.push_error(format!("The color '{name}' is not a valid color name")),
+        };
+
+        ctx.push_string(format!("\x1b[{code}m"));
+        ctx.ret_with(1)
+    }
+
+    extern "C-unwind" fn style(ctx: luau::Context) -> luau::FnReturn {
+        let code = match ctx.arg_string_str(1) {
+            "reset" => 0,
+            "bold" => 1,
+            "dim" => 2,
+            name => ctx.push_error(format!("The style '{name}' is not a valid style name")),
+        };
+
+        ctx.push_string(format!("\x1b[{code}m"));
+        ctx.ret_with(1)
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{globals, library, luau};
+
+pub struct Task;
+library!(Task, spawn, defer, delay, wait, cancel);
+
+impl Task {
+    extern "C-unwind" fn spawn(ctx: luau::Context) -> luau::FnReturn {
+        globals::task::Task::spawn(ctx)
+    }
+
+    extern "C-unwind" fn defer(ctx: luau::Context) -> luau::FnReturn {
+        globals::task::Task::defer(ctx)
+    }
+
+    extern "C-unwind" fn delay(ctx: luau::Context) -> luau::FnReturn {
+        globals::task::Task::delay(ctx)
+    }
+
+    extern "C-unwind" fn wait(ctx: luau::Context) -> luau::FnReturn {
+        globals::task::Task::wait(ctx)
+    }
+
+    extern "C-unwind" fn cancel(ctx: luau::Context) -> luau::FnReturn {
+        globals::task::Task::cancel(ctx)
+    }
+}
@@ -1 +1,21 @@
+use crate::luau::{self, Library};
+
+pub mod codec;
 pub mod fs;
+pub mod lune;
+
+/// Serves the standard libraries under `@bre`.
+pub struct Libs;
+
+impl luau::ModuleProvider for Libs {
+    fn is_module(&self, path: &str) -> bool {
+        matches!(path, "fs")
+    }
+
+    fn load(&self, path: &str) -> Result<luau::Module, String> {
+        match path {
+            "fs" => Ok(luau::Module::Value(Box::new(fs::Fs::push))),
+            _ => Err(format!("no library '@bre/{path}'")),
+        }
+    }
+}
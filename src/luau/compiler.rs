@@ -50,10 +50,15 @@ impl TryFrom<u8> for DebugLevel {
     }
 }
 
+pub fn codegen_supported() -> bool {
+    unsafe { ffi::luau_codegen_supported() != 0 }
+}
+
 #[derive(Default, Clone)]
 pub struct Compiler {
     opt_level: OptLevel,
     dbg_level: DebugLevel,
+    codegen: bool,
 }
 
 impl Compiler {
@@ -67,6 +72,15 @@ impl Compiler {
         self
     }
 
+    pub fn with_codegen(mut self, codegen: bool) -> Self {
+        self.codegen = codegen;
+        self
+    }
+
+    pub fn codegen(&self) -> bool {
+        self.codegen && codegen_supported()
+    }
+
     pub fn compile(&self, source: &[u8]) -> Bytecode {
         use std::ptr::null;
 
@@ -104,6 +118,28 @@ impl Bytecode {
     pub fn inner(&self) -> &[u8] {
         unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
     }
+
+    pub fn error(&self) -> Option<&str> {
+        match self.inner() {
+            [0, message @ ..] => Some(std::str::from_utf8(message).unwrap_or("unknown error")),
+            _ => None,
+        }
+    }
+}
+
+impl From<&[u8]> for Bytecode {
+    fn from(bytes: &[u8]) -> Self {
+        unsafe {
+            let ptr = libc::malloc(bytes.len().max(1)) as *mut u8;
+            assert!(!ptr.is_null(), "out of memory");
+            ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+
+            Self {
+                ptr,
+                len: bytes.len(),
+            }
+        }
+    }
 }
 
 impl Drop for Bytecode {
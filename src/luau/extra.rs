@@ -139,4 +139,40 @@ impl Context {
     pub fn yld_with(self, n: u32) -> FnReturn {
         unsafe { ffi::lua_yield(self.as_ptr(), n as _) }
     }
+
+    /// Yields until `future` completes, then resumes the calling thread with
+    /// its values, or throws its error.
+    pub fn yield_async<T: Push + 'static, E: PushError + 'static>(
+        self,
+        future: impl Future<Output = Result<T, E>> + 'static,
+    ) -> FnReturn {
+        self.park_async(future);
+        self.yld()
+    }
+
+    /// Parks the calling thread on `future` like `yield_async` without
+    /// yielding, returning the id `Main::set_referenced` takes.
+    pub fn park_async<T: Push + 'static, E: PushError + 'static>(
+        &self,
+        future: impl Future<Output = Result<T, E>> + 'static,
+    ) -> u64 {
+        let main = self.main();
+        let resume = move |thread: &Thread, result: Result<T, E>| {
+            let stack = thread.stack();
+
+            match result {
+                Ok(values) => {
+                    let nargs = values.push(&stack);
+                    main.spawn(thread, nargs);
+                }
+
+                Err(e) => {
+                    e.push_error(&stack);
+                    main.spawn_error(thread);
+                }
+            }
+        };
+
+        self.main().park(self.thread().to_ref(), future, resume)
+    }
 }
@@ -108,6 +108,7 @@ unsafe extern "C-unwind" {
     pub fn lua_close(L: *mut lua_State);
     pub fn lua_newthread(L: *mut lua_State) -> *mut lua_State;
     pub fn lua_mainthread(L: *mut lua_State) -> *mut lua_State;
+    pub fn lua_resetthread(L: *mut lua_State);
 
     pub fn lua_getinfo(
         L: *mut lua_State,
@@ -131,6 +132,8 @@ unsafe extern "C-unwind" {
         errfunc: c_int,
     ) -> lua_Status;
 
+    pub fn lua_call(L: *mut lua_State, nargs: c_int, nresults: c_int);
+
     pub fn lua_error(L: *mut lua_State) -> !;
 
     pub fn lua_resume(L: *mut lua_State, from: *mut lua_State, narg: c_int) -> lua_Status;
@@ -179,9 +182,16 @@ unsafe extern "C-unwind" {
     pub fn lua_setreadonly(L: *mut lua_State, idx: c_int, enabled: c_int);
 
     pub fn lua_objlen(L: *mut lua_State, idx: c_int) -> usize;
+    pub fn lua_next(L: *mut lua_State, idx: c_int) -> c_int;
+    pub fn lua_concat(L: *mut lua_State, n: c_int);
 
     pub fn lua_newbuffer(L: *mut lua_State, size: usize) -> *mut c_void;
     pub fn lua_newuserdatatagged(L: *mut lua_State, size: usize, tag: c_int) -> *mut c_void;
+    pub fn lua_newuserdatadtor(
+        L: *mut lua_State,
+        size: usize,
+        dtor: unsafe extern "C" fn(*mut c_void),
+    ) -> *mut c_void;
 
     pub fn lua_pushcclosurek(
         L: *mut lua_State,
@@ -195,11 +205,13 @@ unsafe extern "C-unwind" {
     pub fn lua_unref(L: *mut lua_State, r#ref: c_int);
 
     pub fn lua_type(L: *mut lua_State, idx: c_int) -> lua_Type;
+    pub fn lua_rawequal(L: *mut lua_State, idx1: c_int, idx2: c_int) -> c_int;
     pub fn lua_tonumberx(L: *mut lua_State, idx: c_int, isnum: *mut c_int) -> c_double;
     pub fn lua_tovector(L: *mut lua_State, idx: c_int) -> *const c_float;
     pub fn lua_toboolean(L: *mut lua_State, idx: c_int) -> c_int;
     pub fn lua_tolstring(L: *mut lua_State, idx: c_int, len: *mut usize) -> *const c_char;
     pub fn lua_tolightuserdata(L: *mut lua_State, idx: c_int) -> *mut c_void;
+    pub fn lua_touserdata(L: *mut lua_State, idx: c_int) -> *mut c_void;
     pub fn lua_touserdatatagged(L: *mut lua_State, idx: c_int, tag: c_int) -> *mut c_void;
     pub fn lua_tothread(L: *mut lua_State, idx: c_int) -> *mut lua_State;
     pub fn lua_tobuffer(L: *mut lua_State, idx: c_int, len: *mut usize) -> *mut c_void;
@@ -0,0 +1,9 @@
+use std::ffi::c_int;
+
+use super::lua_State;
+
+unsafe extern "C-unwind" {
+    pub fn luau_codegen_supported() -> c_int;
+    pub fn luau_codegen_create(L: *mut lua_State);
+    pub fn luau_codegen_compile(L: *mut lua_State, idx: c_int);
+}
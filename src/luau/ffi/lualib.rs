@@ -1,4 +1,4 @@
-use std::ffi::c_int;
+use std::ffi::{c_char, c_int};
 
 use super::lua_State;
 
@@ -15,6 +15,9 @@ unsafe extern "C-unwind" {
     pub fn luaopen_debug(L: *mut lua_State) -> c_int;
     pub fn luaopen_vector(L: *mut lua_State) -> c_int;
 
+    pub fn luaL_tolstring(L: *mut lua_State, idx: c_int, len: *mut usize) -> *const c_char;
+    pub fn luaL_where(L: *mut lua_State, lvl: c_int);
+
     pub fn luaL_sandbox(L: *mut lua_State);
     pub fn luaL_sandboxthread(L: *mut lua_State);
 }
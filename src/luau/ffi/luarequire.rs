@@ -83,22 +83,26 @@ pub struct luarequire_Configuration {
 
     pub is_config_present: extern "C-unwind" fn(L: *mut lua_State, ctx: *mut c_void) -> bool,
 
-    pub get_alias: extern "C-unwind" fn(
-        L: *mut lua_State,
-        ctx: *mut c_void,
-        alias: *const c_char,
-        buffer: *mut c_char,
-        buffer_size: usize,
-        size_out: *mut usize,
-    ) -> luarequire_WriteResult,
-
-    pub get_config: extern "C-unwind" fn(
-        L: *mut lua_State,
-        ctx: *mut c_void,
-        buffer: *mut c_char,
-        buffer_size: usize,
-        size_out: *mut usize,
-    ) -> luarequire_WriteResult,
+    pub get_alias: Option<
+        extern "C-unwind" fn(
+            L: *mut lua_State,
+            ctx: *mut c_void,
+            alias: *const c_char,
+            buffer: *mut c_char,
+            buffer_size: usize,
+            size_out: *mut usize,
+        ) -> luarequire_WriteResult,
+    >,
+
+    pub get_config: Option<
+        extern "C-unwind" fn(
+            L: *mut lua_State,
+            ctx: *mut c_void,
+            buffer: *mut c_char,
+            buffer_size: usize,
+            size_out: *mut usize,
+        ) -> luarequire_WriteResult,
+    >,
 
     pub load: extern "C-unwind" fn(
         L: *mut lua_State,
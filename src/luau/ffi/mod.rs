@@ -1,9 +1,11 @@
 mod lua;
 mod luacode;
+mod luacodegen;
 mod lualib;
 mod luarequire;
 
 pub use lua::*;
 pub use luacode::*;
+pub use luacodegen::*;
 pub use lualib::*;
 pub use luarequire::*;
@@ -22,6 +22,11 @@ macro_rules! library {
 
 					let funcname = const {
 						let bytes = concat!(stringify!($func), "\0").as_bytes();
+						// raw identifiers name functions after keywords, like `r#ref`
+						let bytes = match bytes {
+							[b'r', b'#', rest @ ..] => rest,
+							_ => bytes,
+						};
 						match ::std::ffi::CStr::from_bytes_with_nul(bytes) {
 							Ok(cstr) => cstr,
 							Err(_) => unreachable!(),
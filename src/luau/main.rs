@@ -1,9 +1,75 @@
-use std::{ffi::CString, path::PathBuf, ptr::NonNull};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    ffi::CString,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    pin::Pin,
+    ptr::NonNull,
+    task::{Poll, Waker},
+};
 
 use super::*;
 
 pub struct Main(pub(super) NonNull<ffi::lua_State>);
 
+/// How many times deferred threads may defer again before `task.defer`
+/// errors, as in Roblox.
+pub const MAX_DEFER_DEPTH: u32 = 80;
+
+/// Threads queued by `task.defer`, and threads waiting on async builtins. A
+/// resumption cycle is a resume started by the executor; its deferred threads
+/// run in order once it finishes, before the executor moves on, along with
+/// anything they defer in turn.
+#[derive(Default)]
+pub(super) struct Scheduler {
+    deferred: RefCell<VecDeque<(Ref, u32, u32)>>,
+
+    /// Resumes in progress, so only the outermost one runs deferred threads.
+    resuming: Cell<u32>,
+
+    /// The re-entrancy depth of the deferred thread being resumed.
+    depth: Cell<u32>,
+
+    /// Futures threads are parked on, by id.
+    parked: RefCell<HashMap<u64, Parked>>,
+    next_parked: Cell<u64>,
+
+    /// Threads that yielded to wait on an async builtin, until resumed.
+    suspended: RefCell<HashSet<*mut ffi::lua_State>>,
+
+    /// Coroutines that were suspended while being resumed from Luau, and
+    /// the threads waiting for them to yield or return.
+    resumers: RefCell<HashMap<*mut ffi::lua_State, Ref>>,
+
+    /// Context for the error each thread is propagating, reported after its
+    /// traceback if the thread dies with that error uncaught.
+    error_notes: RefCell<HashMap<*mut ffi::lua_State, (Ref, String)>>,
+}
+
+/// Resolves to a call resuming the parked thread.
+type Resumption = Pin<Box<dyn Future<Output = Box<dyn FnOnce()>>>>;
+
+/// A future a thread is parked on, owned here rather than by the executor
+/// so cancelling the thread can drop it, and the refs it holds, right away.
+struct Parked {
+    thread: *mut ffi::lua_State,
+
+    /// Taken out while polled.
+    future: Option<Resumption>,
+    waker: Option<Waker>,
+    task: Option<crate::runtime::TaskId>,
+}
+
+impl Scheduler {
+    pub(super) fn clear(&self) {
+        self.deferred.borrow_mut().clear();
+        self.parked.borrow_mut().clear();
+        self.resumers.borrow_mut().clear();
+        self.error_notes.borrow_mut().clear();
+    }
+}
+
 impl Main {
     pub fn inner(&self) -> NonNull<ffi::lua_State> {
         self.0
@@ -45,39 +111,371 @@ impl Main {
 
     pub fn handle_status(&self, thread: &Thread, status: Status) {
         match status {
-            Status::Ok => {}
+            Status::Ok => {
+                let scheduler = &self.data().scheduler;
+                scheduler.error_notes.borrow_mut().remove(&thread.as_ptr());
+            }
+
             Status::Yield => {}
 
             _ => {
-                let stack = thread.stack();
-                let err = stack.to_string_str(-1).unwrap_or("unknown error");
-                let trace = unsafe {
-                    let ptr = ffi::lua_debugtrace(thread.as_ptr());
-                    std::str::from_utf8_unchecked(std::ffi::CStr::from_ptr(ptr).to_bytes())
-                };
+                let stack = self.stack();
+                thread.stack().xpush(&Thread(self.0), -1);
+
+                let err = self.error_message(thread);
+                let note = self.take_error_note(thread, &stack, -1);
+                stack.pop(1);
+
+                let trace = thread.traceback();
 
                 eprint!("{err}\ntraceback:\n{trace}");
+
+                if let Some(note) = note {
+                    eprintln!("{}", note.trim_end());
+                }
             }
         }
     }
 
+    /// Formats the error on top of `thread`'s stack, which died with it.
+    pub fn error_message(&self, thread: &Thread) -> String {
+        // the errored thread can't run the __tostring metamethod
+        let stack = self.stack();
+        thread.stack().xpush(&Thread(self.0), -1);
+
+        let err = stack.to_display(-1);
+        stack.pop(1);
+
+        err
+    }
+
+    /// Attaches `note` to the error at `idx` on `stack`, to report with it if
+    /// `thread` dies with that error uncaught. Replaces the thread's previous
+    /// note.
+    pub fn set_error_note(&self, thread: &Thread, stack: &Stack, idx: i32, note: String) {
+        let scheduler = &self.data().scheduler;
+        let err = stack.to_ref(idx);
+
+        scheduler
+            .error_notes
+            .borrow_mut()
+            .insert(thread.as_ptr(), (err, note));
+    }
+
+    /// Removes the note attached to `thread`'s error, returning it if it was
+    /// attached to the error at `idx` on `stack`.
+    pub fn take_error_note(&self, thread: &Thread, stack: &Stack, idx: i32) -> Option<String> {
+        let scheduler = &self.data().scheduler;
+        let (err, note) = scheduler
+            .error_notes
+            .borrow_mut()
+            .remove(&thread.as_ptr())?;
+
+        stack.push_ref(&err);
+        let same = stack.raw_equal(if idx < 0 { idx - 1 } else { idx }, -1);
+        stack.pop(1);
+
+        same.then_some(note)
+    }
+
     pub fn spawn(&self, thread: &Thread, nargs: u32) {
-        self.handle_status(thread, thread.resume(None, nargs));
+        self.resume(thread, || thread.resume(None, nargs));
     }
 
     pub fn spawn_error(&self, thread: &Thread) {
-        self.handle_status(thread, thread.resume_error(None));
+        self.resume(thread, || thread.resume_error(None));
     }
 
-    pub fn execute(&self, path: PathBuf, bytecode: &Bytecode) -> (Status, Stack) {
-        let (_, thread) = self.new_thread();
-        let stack = thread.stack();
+    fn resume(&self, thread: &Thread, resume: impl FnOnce() -> Status) {
+        let scheduler = &self.data().scheduler;
 
-        stack.push_bytecode(
-            &unsafe { CString::from_vec_unchecked(path.display().to_string().into_bytes()) },
-            bytecode,
+        scheduler.suspended.borrow_mut().remove(&thread.as_ptr());
+
+        scheduler.resuming.set(scheduler.resuming.get() + 1);
+        self.finish(thread, resume());
+        scheduler.resuming.set(scheduler.resuming.get() - 1);
+
+        if scheduler.resuming.get() == 0 {
+            self.run_deferred();
+        }
+    }
+
+    /// Hands control back to the thread that resumed `thread` as a coroutine
+    /// once it yields or returns for real, or reports how it ended.
+    fn finish(&self, thread: &Thread, status: Status) {
+        let scheduler = &self.data().scheduler;
+
+        let resumer = match self.is_suspended(thread) {
+            true => None,
+            false => scheduler.resumers.borrow_mut().remove(&thread.as_ptr()),
+        };
+
+        match resumer {
+            Some(r) => {
+                let resumer = r.to_thread();
+                resumer.stack().push_thread(thread);
+                self.resume(&resumer, || resumer.resume(None, 1));
+            }
+
+            None => self.handle_status(thread, status),
+        }
+    }
+
+    /// Marks `thread`, which is about to yield, as waiting on an async
+    /// builtin. Only the scheduler resumes it, and a coroutine resumer
+    /// running it yields in turn until it's done.
+    pub fn suspend(&self, thread: &Thread) {
+        self.data()
+            .scheduler
+            .suspended
+            .borrow_mut()
+            .insert(thread.as_ptr());
+    }
+
+    pub fn is_suspended(&self, thread: &Thread) -> bool {
+        self.data()
+            .scheduler
+            .suspended
+            .borrow()
+            .contains(&thread.as_ptr())
+    }
+
+    /// Suspends `resumer` until the suspended coroutine `co` yields or
+    /// returns for real, then resumes it with `co` as its only argument.
+    pub fn await_coroutine(&self, resumer: &Thread, co: &Thread) {
+        let scheduler = &self.data().scheduler;
+
+        scheduler
+            .resumers
+            .borrow_mut()
+            .insert(co.as_ptr(), resumer.to_ref());
+
+        self.suspend(resumer);
+    }
+
+    /// Queues `thread` to be resumed with the `nargs` values on its stack at
+    /// the end of the current resumption cycle.
+    pub fn defer(&self, thread: Ref, nargs: u32) -> Result<(), String> {
+        let scheduler = &self.data().scheduler;
+
+        let depth = scheduler.depth.get() + 1;
+        if depth > MAX_DEFER_DEPTH {
+            return Err(format!(
+                "maximum re-entrancy depth ({MAX_DEFER_DEPTH}) exceeded calling task.defer"
+            ));
+        }
+
+        let idle = scheduler.resuming.get() == 0 && scheduler.deferred.borrow().is_empty();
+        scheduler
+            .deferred
+            .borrow_mut()
+            .push_back((thread, nargs, depth));
+
+        // deferred outside of any resumption, so nothing would run the queue
+        if idle {
+            let main = Main(self.0);
+            self.spawner().defer(async move { main.run_deferred() });
+        }
+
+        Ok(())
+    }
+
+    /// Resumes `thread` through `resume` once `future` completes. If the
+    /// thread is cancelled first, the future is dropped without resuming it.
+    /// A running thread is marked suspended, as it's about to yield. Returns
+    /// an id for `set_referenced`.
+    pub fn park<T: 'static>(
+        &self,
+        thread: Ref,
+        future: impl Future<Output = T> + 'static,
+        resume: impl FnOnce(&Thread, T) + 'static,
+    ) -> u64 {
+        let scheduler = &self.data().scheduler;
+
+        let id = scheduler.next_parked.get();
+        scheduler.next_parked.set(id + 1);
+
+        let ptr = thread.to_thread().as_ptr();
+        if thread.to_thread().coro_status() == CoroStatus::Run {
+            self.suspend(&thread.to_thread());
+        }
+
+        let future = async move {
+            let value = future.await;
+            Box::new(move || resume(&thread.to_thread(), value)) as Box<dyn FnOnce()>
+        };
+
+        scheduler.parked.borrow_mut().insert(
+            id,
+            Parked {
+                thread: ptr,
+                future: Some(Box::pin(future)),
+                waker: None,
+                task: None,
+            },
         );
 
-        (thread.resume(None, 0), stack)
+        // deferred, so a future that's already done can't resume the thread
+        // before it yields
+        let main = Main(self.0);
+        let task = self.spawner().defer(async move {
+            let scheduler = &main.data().scheduler;
+
+            let resume = std::future::poll_fn(|cx| {
+                let future = scheduler
+                    .parked
+                    .borrow_mut()
+                    .get_mut(&id)
+                    .and_then(|parked| parked.future.take());
+
+                // cancelled
+                let Some(mut future) = future else {
+                    return Poll::Ready(None);
+                };
+
+                let poll = future.as_mut().poll(cx);
+                let mut parked = scheduler.parked.borrow_mut();
+
+                match (parked.get_mut(&id), poll) {
+                    // cancelled by code the future resumed
+                    (None, _) => Poll::Ready(None),
+
+                    (Some(_), Poll::Ready(resume)) => {
+                        parked.remove(&id);
+                        Poll::Ready(Some(resume))
+                    }
+
+                    (Some(entry), Poll::Pending) => {
+                        entry.future = Some(future);
+                        entry.waker = Some(cx.waker().clone());
+                        Poll::Pending
+                    }
+                }
+            })
+            .await;
+
+            if let Some(resume) = resume {
+                resume();
+            }
+        });
+
+        if let Some(parked) = scheduler.parked.borrow_mut().get_mut(&id) {
+            parked.task = Some(task);
+        }
+
+        id
+    }
+
+    pub fn is_parked(&self, id: u64) -> bool {
+        self.data().scheduler.parked.borrow().contains_key(&id)
+    }
+
+    /// Sets whether a parked future keeps the process running. Returns false
+    /// if it already finished.
+    pub fn set_referenced(&self, id: u64, referenced: bool) -> bool {
+        let parked = self.data().scheduler.parked.borrow();
+
+        let Some(task) = parked.get(&id).and_then(|parked| parked.task.as_ref()) else {
+            return false;
+        };
+
+        self.spawner().set_referenced(task, referenced);
+        true
+    }
+
+    /// Sets whether everything `thread` is parked on keeps the process
+    /// running.
+    pub fn set_thread_referenced(&self, thread: &Thread, referenced: bool) {
+        let parked = self.data().scheduler.parked.borrow();
+
+        for parked in parked.values().filter(|p| p.thread == thread.as_ptr()) {
+            if let Some(task) = &parked.task {
+                self.spawner().set_referenced(task, referenced);
+            }
+        }
+    }
+
+    /// Keeps the process running with nothing pending, until turned off.
+    pub fn keep_alive(&self, keep_alive: bool) {
+        self.spawner().keep_alive(keep_alive);
+    }
+
+    /// Drops the futures `thread` is parked on and removes it from the
+    /// deferred queue, so nothing resumes it again.
+    pub fn cancel(&self, thread: &Thread) {
+        let scheduler = &self.data().scheduler;
+
+        let cancelled: Vec<_> = scheduler
+            .parked
+            .borrow_mut()
+            .extract_if(|_, parked| parked.thread == thread.as_ptr())
+            .map(|(_, parked)| parked)
+            .collect();
+
+        // dropping the futures releases their refs and timers, and waking
+        // their tasks lets the executor forget them
+        for parked in cancelled {
+            if let Some(waker) = parked.waker {
+                waker.wake();
+            }
+        }
+
+        scheduler
+            .deferred
+            .borrow_mut()
+            .retain(|(r, ..)| r.to_thread().as_ptr() != thread.as_ptr());
+
+        scheduler.suspended.borrow_mut().remove(&thread.as_ptr());
+        scheduler.error_notes.borrow_mut().remove(&thread.as_ptr());
+        scheduler
+            .resumers
+            .borrow_mut()
+            .retain(|_, r| r.to_thread().as_ptr() != thread.as_ptr());
+
+        // whoever resumed it as a coroutine sees it return
+        let resumer = scheduler.resumers.borrow_mut().remove(&thread.as_ptr());
+        if let Some(r) = resumer {
+            r.to_thread().stack().push_thread(thread);
+
+            let main = Main(self.0);
+            self.spawner().defer(async move {
+                main.spawn(&r.to_thread(), 1);
+            });
+        }
+    }
+
+    fn run_deferred(&self) {
+        let scheduler = &self.data().scheduler;
+
+        loop {
+            let Some((r, nargs, depth)) = scheduler.deferred.borrow_mut().pop_front() else {
+                break;
+            };
+
+            let thread = r.to_thread();
+
+            scheduler.suspended.borrow_mut().remove(&thread.as_ptr());
+
+            scheduler.depth.set(depth);
+            scheduler.resuming.set(1);
+            self.finish(&thread, thread.resume(None, nargs));
+            scheduler.resuming.set(0);
+        }
+
+        scheduler.depth.set(0);
+    }
+
+    /// Runs the chunk like `Luau::execute`, returning how it ended and a ref
+    /// keeping its thread, which holds its results, alive.
+    pub fn execute(&self, path: &Path, bytecode: &Bytecode) -> (Status, Ref) {
+        let (r, thread) = self.new_thread();
+        let stack = thread.stack();
+
+        let name = CString::new(path.as_os_str().as_bytes()).expect("paths can't contain NUL");
+        stack.push_bytecode(&name, bytecode);
+
+        self.spawn(&thread, 0);
+
+        (thread.status(), r)
     }
 }
@@ -6,23 +6,33 @@ mod compiler;
 mod extra;
 mod library;
 mod main;
+mod provider;
+mod push;
 mod stack;
 mod thread;
 mod userdata;
 
-pub use compiler::{Bytecode, Compiler};
+pub use compiler::{Bytecode, Compiler, OptLevel, codegen_supported};
 pub use extra::*;
 pub use library::*;
 pub use main::Main;
+pub use provider::{Module, ModuleProvider};
+pub use push::{Push, PushError, PushWith};
 pub use stack::Stack;
 pub use thread::Thread;
 pub use userdata::*;
 
 pub const REGISTRY_IDX: i32 = ffi::LUA_REGISTRYINDEX;
+pub const GLOBALS_IDX: i32 = ffi::LUA_GLOBALSINDEX;
+
+pub const fn upvalue_idx(i: i32) -> i32 {
+    ffi::lua_upvalueindex(i)
+}
 
 struct LuauData<'executor> {
     spawner: crate::runtime::Spawner<'executor>,
     compiler: Compiler,
+    scheduler: main::Scheduler,
 }
 
 pub struct Luau<'executor> {
@@ -31,7 +41,11 @@ pub struct Luau<'executor> {
 }
 
 impl<'executor> Luau<'executor> {
-    pub fn new(spawner: crate::runtime::Spawner<'executor>, compiler: Compiler) -> Self {
+    pub fn new(
+        spawner: crate::runtime::Spawner<'executor>,
+        compiler: Compiler,
+        require: crate::globals::require::Options,
+    ) -> Self {
         #[allow(unused)]
         unsafe extern "C-unwind" fn lua_alloc(
             _ud: *mut std::ffi::c_void,
@@ -47,7 +61,12 @@ impl<'executor> Luau<'executor> {
             }
         }
 
-        let data = Box::into_raw(Box::new(LuauData { spawner, compiler }));
+        let codegen = compiler.codegen();
+        let data = Box::into_raw(Box::new(LuauData {
+            spawner,
+            compiler,
+            scheduler: Default::default(),
+        }));
         let state = NonNull::new(unsafe { ffi::lua_newstate(lua_alloc, std::ptr::null_mut()) })
             .expect("failed to create lua state");
 
@@ -56,6 +75,10 @@ impl<'executor> Luau<'executor> {
         unsafe {
             ffi::lua_setthreaddata(state.as_ptr(), data as _);
 
+            if codegen {
+                ffi::luau_codegen_create(state.as_ptr());
+            }
+
             ffi::luaopen_base(state.as_ptr());
             ffi::luaopen_coroutine(state.as_ptr());
             ffi::luaopen_table(state.as_ptr());
@@ -68,17 +91,22 @@ impl<'executor> Luau<'executor> {
             ffi::luaopen_debug(state.as_ptr());
             ffi::luaopen_vector(state.as_ptr());
 
-            crate::globals::require::open(Main(state));
+            crate::globals::require::open(
+                Main(state),
+                require.with_provider("bre", crate::libs::Libs),
+            );
 
             let stack = Stack(state);
             stack.pop(11);
 
+            crate::libs::fs::File::register(&Main(state));
+            crate::libs::fs::Walker::register(&Main(state));
+            crate::libs::fs::Watcher::register(&Main(state));
+
             crate::globals::task::Task::push(Stack(state));
             stack.table_set_raw_field(ffi::LUA_GLOBALSINDEX, c"task");
 
-            stack.push_string("@bre/fs");
-            crate::libs::fs::Fs::push(Stack(state));
-            ffi::luarequire_registermodule(state.as_ptr());
+            crate::globals::coroutine::open(&stack);
 
             ffi::luaL_sandbox(state.as_ptr());
         }
@@ -86,13 +114,17 @@ impl<'executor> Luau<'executor> {
         luau
     }
 
+    pub fn main(&self) -> Main {
+        Main(self.state)
+    }
+
     pub fn execute(&self, path: &Path, bytecode: &Bytecode) {
         let main = Main(self.state);
 
         let (_, thread) = main.new_thread();
         let stack = thread.stack();
 
-        let name = CString::new(path.to_str().unwrap()).unwrap();
+        let name = CString::new(path.as_os_str().as_encoded_bytes()).unwrap();
 
         stack.push_bytecode(name.as_c_str(), bytecode);
         main.spawn(&thread, 0);
@@ -102,6 +134,9 @@ impl<'executor> Luau<'executor> {
 impl Drop for Luau<'_> {
     fn drop(&mut self) {
         unsafe {
+            // the queued and parked refs have to be released while the state is alive
+            (*self.data).scheduler.clear();
+
             ffi::lua_close(self.state.as_ptr());
             drop(Box::from_raw(self.data as *mut LuauData));
         }
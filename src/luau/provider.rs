@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// What a [`ModuleProvider`] hands back for a module.
+pub enum Module {
+    /// Luau source, compiled with the runtime's compiler.
+    Source(Vec<u8>),
+
+    /// Bytecode produced ahead of time, by [`Compiler::compile`] or `luau-compile`.
+    Bytecode(Bytecode),
+
+    /// Pushes the module value directly, such as a [`Library`] table.
+    Value(Box<dyn FnOnce(Stack)>),
+}
+
+/// Serves modules that do not live on the filesystem. A provider is mounted
+/// under an alias, so `require("@alias/path/to/module")` reaches it.
+///
+/// Paths are relative to the alias and joined with `/`, the root being the
+/// empty string. The chunkname of a provided module is `@alias/path`, which
+/// is also what relative requires inside it navigate from.
+pub trait ModuleProvider {
+    /// Whether `path` names a module.
+    fn is_module(&self, path: &str) -> bool;
+
+    /// Whether `path` contains other modules that can be navigated to.
+    fn is_directory(&self, path: &str) -> bool {
+        path.is_empty()
+    }
+
+    fn load(&self, path: &str) -> Result<Module, String>;
+}
+
+/// Serves source held in memory, keyed by path.
+impl ModuleProvider for BTreeMap<String, String> {
+    fn is_module(&self, path: &str) -> bool {
+        self.contains_key(path)
+    }
+
+    fn is_directory(&self, path: &str) -> bool {
+        path.is_empty()
+            || self.keys().any(|key| {
+                key.strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+
+    fn load(&self, path: &str) -> Result<Module, String> {
+        match self.get(path) {
+            Some(source) => Ok(Module::Source(source.clone().into_bytes())),
+            None => Err(format!("no module at '{path}'")),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{convert::Infallible, os::unix::ffi::OsStrExt, path::PathBuf};
+
+use super::*;
+
+/// Values that can be pushed onto a stack, returning how many were pushed.
+pub trait Push {
+    fn push(self, stack: &Stack) -> u32;
+}
+
+/// Errors that can be thrown, pushing the single value raised.
+pub trait PushError {
+    fn push_error(self, stack: &Stack);
+}
+
+/// Pushes whatever the wrapped closure leaves on the stack, for values
+/// without a `Push` impl of their own.
+pub struct PushWith<F>(pub F);
+
+impl<F: FnOnce(&Stack) -> u32> Push for PushWith<F> {
+    fn push(self, stack: &Stack) -> u32 {
+        (self.0)(stack)
+    }
+}
+
+impl Push for () {
+    fn push(self, _: &Stack) -> u32 {
+        0
+    }
+}
+
+impl Push for Infallible {
+    fn push(self, _: &Stack) -> u32 {
+        match self {}
+    }
+}
+
+impl PushError for Infallible {
+    fn push_error(self, _: &Stack) {
+        match self {}
+    }
+}
+
+impl PushError for String {
+    fn push_error(self, stack: &Stack) {
+        stack.push_string(self);
+    }
+}
+
+impl PushError for &'static str {
+    fn push_error(self, stack: &Stack) {
+        stack.push_string(self);
+    }
+}
+
+impl Push for bool {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_boolean(self);
+        1
+    }
+}
+
+impl Push for f64 {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_number(self);
+        1
+    }
+}
+
+impl Push for String {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_string(self);
+        1
+    }
+}
+
+impl Push for &'static str {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_string(self);
+        1
+    }
+}
+
+impl Push for Vec<u8> {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_string(self);
+        1
+    }
+}
+
+impl Push for PathBuf {
+    fn push(self, stack: &Stack) -> u32 {
+        stack.push_string(self.as_os_str().as_bytes());
+        1
+    }
+}
+
+impl<T: Push> Push for Option<T> {
+    fn push(self, stack: &Stack) -> u32 {
+        match self {
+            Some(value) => value.push(stack),
+
+            None => {
+                stack.push_nil();
+                1
+            }
+        }
+    }
+}
+
+macro_rules! push_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Push),*> Push for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn push(self, stack: &Stack) -> u32 {
+                let ($($name,)*) = self;
+                0 $(+ $name.push(stack))*
+            }
+        }
+    };
+}
+
+push_tuple!(A);
+push_tuple!(A, B);
+push_tuple!(A, B, C);
+push_tuple!(A, B, C, D);
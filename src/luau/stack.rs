@@ -1,5 +1,7 @@
 use std::{
-    ffi::{CStr, c_int, c_void},
+    ffi::{CStr, OsStr, c_int, c_void},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
     ptr::NonNull,
 };
 
@@ -72,6 +74,16 @@ impl Stack {
         unsafe { ffi::lua_remove(self.as_ptr(), idx as _) };
     }
 
+    pub fn concat(&self, n: u32) {
+        unsafe { ffi::lua_concat(self.as_ptr(), n as _) };
+    }
+
+    /// Pushes the `chunkname:line: ` location of the function `level` calls
+    /// up, or an empty string if it isn't Luau code.
+    pub fn push_where(&self, level: u32) {
+        unsafe { ffi::luaL_where(self.as_ptr(), level as _) };
+    }
+
     pub fn insert(&self, idx: i32) {
         unsafe { ffi::lua_insert(self.as_ptr(), idx as _) };
     }
@@ -157,6 +169,30 @@ impl Stack {
             ffi::lua_pushcclosurek(self.as_ptr(), func, name.as_ptr() as _, 0, Some(cont));
         }
     }
+    /// Pushes `func` closing over the top `n` values, which it reads with
+    /// `upvalue_idx`.
+    pub fn push_closure_cont(
+        &self,
+        name: &'static CStr,
+        func: extern "C-unwind" fn(ctx: Context) -> FnReturn,
+        cont: extern "C-unwind" fn(ctx: Context, status: Status) -> FnReturn,
+        n: u32,
+    ) {
+        unsafe {
+            let func = std::mem::transmute::<
+                extern "C-unwind" fn(ctx: Context) -> FnReturn,
+                extern "C-unwind" fn(*mut ffi::lua_State) -> FnReturn,
+            >(func);
+
+            let cont = std::mem::transmute::<
+                extern "C-unwind" fn(ctx: Context, status: Status) -> FnReturn,
+                extern "C-unwind" fn(*mut ffi::lua_State, status: c_int) -> FnReturn,
+            >(cont);
+
+            ffi::lua_pushcclosurek(self.as_ptr(), func, name.as_ptr() as _, n as _, Some(cont));
+        }
+    }
+
     pub fn push_bytecode(&self, name: &CStr, bytecode: &Bytecode) {
         unsafe {
             ffi::luau_load(
@@ -166,6 +202,10 @@ impl Stack {
                 bytecode.inner().len() as _,
                 0,
             );
+
+            if self.main().compiler().codegen() {
+                ffi::luau_codegen_compile(self.as_ptr(), -1);
+            }
         }
     }
 
@@ -266,6 +306,10 @@ impl Stack {
         self.type_of(idx) == Type::Buffer
     }
 
+    pub fn raw_equal(&self, idx1: i32, idx2: i32) -> bool {
+        unsafe { ffi::lua_rawequal(self.as_ptr(), idx1 as _, idx2 as _) != 0 }
+    }
+
     pub fn to_boolean(&self, idx: i32) -> Option<bool> {
         if self.is_boolean(idx) {
             Some(unsafe { ffi::lua_toboolean(self.as_ptr(), idx as _) != 0 })
@@ -310,6 +354,33 @@ impl Stack {
         }
     }
 
+    /// Converts any value to a string the way `tostring` does, respecting
+    /// `__tostring` metamethods. The metamethod runs protected, falling back
+    /// to the type name if it errors. Must not be called on a thread that
+    /// has errored.
+    pub fn to_display(&self, idx: i32) -> String {
+        extern "C-unwind" fn tostring(ctx: Context) -> FnReturn {
+            unsafe { ffi::luaL_tolstring(ctx.as_ptr(), 1, std::ptr::null_mut()) };
+            ctx.ret_with(1)
+        }
+
+        let ty = self.type_of(idx);
+
+        self.push_copy(idx);
+        self.push_function(c"tostring", tostring);
+        self.insert(-2);
+
+        let s = match self.pcall(1, 1, 0) {
+            Status::Ok => self.to_string_slice(-1).map(String::from_utf8_lossy),
+            _ => None,
+        }
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|| format!("(error object is a {ty} value)"));
+
+        self.pop(1);
+        s
+    }
+
     pub fn to_string_str(&self, idx: i32) -> Option<&str> {
         std::str::from_utf8(self.to_string_slice(idx)?).ok()
     }
@@ -449,6 +520,22 @@ impl Stack {
         }
     }
 
+    /// Accepts a string or buffer holding the raw bytes of a path, which need
+    /// not be valid utf8.
+    pub fn arg_path(&self, idx: u32) -> PathBuf {
+        let bytes = match self.type_of(idx as _) {
+            Type::String => self.to_string_slice(idx as _).unwrap(),
+            Type::Buffer => self.to_buffer(idx as _).unwrap(),
+
+            ty => self.push_error(format!(
+                "bad argument #{idx} to function (string or buffer expected, got {})",
+                ty
+            )),
+        };
+
+        PathBuf::from(OsStr::from_bytes(bytes))
+    }
+
     pub fn arg_string_opt_slice(&self, idx: u32) -> Option<&[u8]> {
         if let Some(s) = self.to_string_slice(idx as _) {
             Some(s)
@@ -620,7 +707,19 @@ impl Stack {
         unsafe { ffi::lua_rawsetfield(self.as_ptr(), tbl_idx as _, key.as_ptr() as _) };
     }
 
+    pub fn push_metatable(&self, idx: i32) -> bool {
+        unsafe { ffi::lua_getmetatable(self.as_ptr(), idx as _) != 0 }
+    }
+
+    pub fn set_metatable(&self, idx: i32) {
+        unsafe { ffi::lua_setmetatable(self.as_ptr(), idx as _) };
+    }
+
     pub fn len(&self, idx: i32) -> usize {
         unsafe { ffi::lua_objlen(self.as_ptr(), idx as _) }
     }
+
+    pub fn next(&self, tbl_idx: i32) -> bool {
+        unsafe { ffi::lua_next(self.as_ptr(), tbl_idx as _) != 0 }
+    }
 }
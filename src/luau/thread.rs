@@ -41,6 +41,11 @@ impl Thread {
         }
     }
 
+    /// Closes the thread, unwinding its stack so it can't be resumed again.
+    pub fn reset(&self) {
+        unsafe { ffi::lua_resetthread(self.as_ptr()) }
+    }
+
     pub fn status(&self) -> Status {
         unsafe { Status::from(ffi::lua_status(self.as_ptr())) }
     }
@@ -49,6 +54,13 @@ impl Thread {
         unsafe { CoroStatus::from(ffi::lua_costatus(self.as_ptr())) }
     }
 
+    pub fn traceback(&self) -> String {
+        unsafe {
+            let ptr = ffi::lua_debugtrace(self.as_ptr());
+            String::from_utf8_lossy(std::ffi::CStr::from_ptr(ptr).to_bytes()).into_owned()
+        }
+    }
+
     pub fn to_ref(&self) -> Ref {
         let stack = self.stack();
         stack.push_thread(self);
@@ -51,6 +51,11 @@ macro_rules! userdata {
 
 					let methodname = const {
 						let bytes = concat!(stringify!($method), "\0").as_bytes();
+						// raw identifiers name methods after keywords, like `r#ref`
+						let bytes = match bytes {
+							[b'r', b'#', rest @ ..] => rest,
+							_ => bytes,
+						};
 						match ::std::ffi::CStr::from_bytes_with_nul(bytes) {
 							Ok(cstr) => cstr,
 							Err(_) => unreachable!(),
@@ -59,7 +64,7 @@ macro_rules! userdata {
 
 					stack.push_function(debugname, Self::$method);
 					stack.table_set_raw_field(-2, methodname);
-				),*
+				)*
 				stack.table_set_raw_field(-2, c"__index");
 
 				unsafe {
@@ -0,0 +1,182 @@
+use std::path::{Component, Path, PathBuf};
+
+pub const LUAURC: &str = ".luaurc";
+
+pub struct Alias {
+    pub name: String,
+    pub target: PathBuf,
+    pub config: PathBuf,
+}
+
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if out.file_name().is_some() => {
+                out.pop();
+            }
+
+            component => out.push(component),
+        }
+    }
+
+    out
+}
+
+/// Resolves an alias value relative to the directory of the config that
+/// declares it, following symlinks for whatever part of it exists.
+fn resolve(dir: &Path, value: &str) -> PathBuf {
+    let path = normalize(&dir.join(value));
+
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path,
+    }
+}
+
+fn read_aliases(config: &Path) -> Result<Vec<(String, String)>, String> {
+    let contents = match std::fs::read(config) {
+        Ok(contents) => contents,
+        // a file along the way means there's no config here either
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory
+            ) =>
+        {
+            return Ok(Vec::new());
+        }
+
+        Err(e) => return Err(format!("failed to read '{}': {e}", config.display())),
+    };
+
+    let value: serde_json::Value = serde_json::from_slice(&contents)
+        .map_err(|e| format!("failed to parse '{}': {e}", config.display()))?;
+
+    let Some(aliases) = value.get("aliases") else {
+        return Ok(Vec::new());
+    };
+
+    let Some(aliases) = aliases.as_object() else {
+        return Err(format!(
+            "'aliases' in '{}' must be an object",
+            config.display()
+        ));
+    };
+
+    aliases
+        .iter()
+        .map(|(name, target)| match target.as_str() {
+            Some(target) => Ok((name.clone(), target.to_owned())),
+            None => Err(format!(
+                "alias '@{name}' in '{}' must be a string",
+                config.display()
+            )),
+        })
+        .collect()
+}
+
+/// Every alias visible from `from`, with aliases declared in closer configs
+/// shadowing those of the same name further up.
+pub fn aliases(from: &Path) -> Result<Vec<Alias>, String> {
+    let mut aliases: Vec<Alias> = Vec::new();
+
+    for dir in from.ancestors() {
+        let config = dir.join(LUAURC);
+
+        for (name, value) in read_aliases(&config)? {
+            if aliases.iter().any(|a| a.name.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+
+            if value.starts_with('@') {
+                return Err(format!(
+                    "alias '@{name}' in '{}' cannot point to another alias",
+                    config.display()
+                ));
+            }
+
+            aliases.push(Alias {
+                name,
+                target: resolve(dir, &value),
+                config: config.clone(),
+            });
+        }
+    }
+
+    Ok(aliases)
+}
+
+/// Finds the closest declaration of `name` in the configs above `from`.
+pub fn find_alias(from: &Path, name: &str) -> Result<Option<Alias>, String> {
+    Ok(aliases(from)?
+        .into_iter()
+        .find(|a| a.name.eq_ignore_ascii_case(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_from_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(root.join("lib")).unwrap();
+        std::fs::write(root.join("main.luau"), "").unwrap();
+        std::fs::write(root.join(LUAURC), r#"{ "aliases": { "lib": "./lib" } }"#).unwrap();
+
+        let found = find_alias(&root.join("main.luau"), "LIB").unwrap().unwrap();
+
+        assert_eq!(found.target, root.join("lib"));
+        assert_eq!(found.config, root.join(LUAURC));
+    }
+
+    #[test]
+    fn closer_configs_shadow() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+
+        std::fs::create_dir_all(root.join("sub/a")).unwrap();
+        std::fs::write(
+            root.join(LUAURC),
+            r#"{ "aliases": { "a": "./x", "b": "./y" } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("sub").join(LUAURC),
+            r#"{ "aliases": { "a": "./a" } }"#,
+        )
+        .unwrap();
+
+        let mut aliases: Vec<_> = aliases(&root.join("sub"))
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.name, a.target))
+            .collect();
+        aliases.sort();
+
+        assert_eq!(
+            aliases,
+            [
+                ("a".to_owned(), root.join("sub/a")),
+                ("b".to_owned(), root.join("y"))
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_alias_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(LUAURC), r#"{ "aliases": { "a": "@b" } }"#).unwrap();
+
+        assert!(aliases(dir.path()).is_err());
+    }
+}
@@ -3,7 +3,11 @@ mod runtime;
 
 mod globals;
 mod libs;
+mod luaurc;
+mod pkg;
+mod transform;
 
+mod bench;
 mod cli;
 
 fn main() {
@@ -0,0 +1,236 @@
+use std::{
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use sha2::{Digest, Sha256};
+
+use super::manifest::{self, MANIFEST, Manifest, Package, Source};
+
+pub struct Fetched {
+    pub dir: PathBuf,
+    pub package: Package,
+    pub manifest: Manifest,
+    pub source: Source,
+    pub hash: String,
+}
+
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to run {command:?}: {e}"))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    } else {
+        Err(format!(
+            "{command:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+
+        if name == ".git" || name == "packages" || name == manifest::LOCKFILE {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+
+        // links are copied as links, so a link to a parent can't recurse
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, to.join(&name))?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &to.join(&name))?;
+        } else {
+            std::fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn fetch_git(repo: &Path, rev: Option<&str>, to: &Path) -> Result<String, String> {
+    let commit = run(Command::new("git").arg("-C").arg(repo).args([
+        "rev-parse",
+        "--verify",
+        &format!("{}^{{commit}}", rev.unwrap_or("HEAD")),
+    ]))?;
+
+    std::fs::create_dir_all(to).map_err(|e| e.to_string())?;
+
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["archive", "--format=tar", &commit])
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+
+    let extract = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(to)
+        .stdin(archive.stdout.take().unwrap())
+        .status()
+        .map_err(|e| format!("failed to run tar: {e}"))?;
+
+    let archived = archive.wait().map_err(|e| e.to_string())?;
+
+    if !archived.success() || !extract.success() {
+        return Err(format!(
+            "failed to export {commit} from '{}'",
+            repo.display()
+        ));
+    }
+
+    Ok(commit)
+}
+
+fn fetch_tarball(tarball: &Path, to: &Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(to).map_err(|e| e.to_string())?;
+    run(Command::new("tar")
+        .arg("-xf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(to))?;
+
+    let entries = std::fs::read_dir(to)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| e.to_string())?;
+
+    match entries.as_slice() {
+        [entry] if entry.path().is_dir() => Ok(entry.path()),
+        _ => Ok(to.to_owned()),
+    }
+}
+
+/// Hashes every file in a package, keyed by its path relative to the package
+/// root so the hash does not depend on where the package was unpacked.
+pub fn hash_dir(root: &Path) -> Result<String, String> {
+    fn visit(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                visit(root, &path, files)?;
+            } else {
+                files.push(path.strip_prefix(root).unwrap().to_owned());
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(root, root, &mut files).map_err(|e| e.to_string())?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+
+    for file in files {
+        let path = root.join(&file);
+        hasher.update(file.to_string_lossy().as_bytes());
+
+        // links hash their target rather than what it points to
+        if path.is_symlink() {
+            let target = std::fs::read_link(&path).map_err(|e| e.to_string())?;
+
+            hasher.update([1]);
+            hasher.update(target.as_os_str().as_bytes());
+            continue;
+        }
+
+        let contents = std::fs::read(&path).map_err(|e| e.to_string())?;
+
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    let digest = hasher.finalize();
+    let hex = digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    Ok(format!("sha256-{hex}"))
+}
+
+/// Copies or unpacks a package from its source into `staging`.
+pub fn fetch(source: &Source, staging: &Path) -> Result<Fetched, String> {
+    let (dir, source) = match source {
+        Source::Path(path) => {
+            copy_dir(path, staging)
+                .map_err(|e| format!("failed to copy '{}': {e}", path.display()))?;
+
+            (staging.to_owned(), source.clone())
+        }
+
+        Source::Git(repo, rev) => {
+            let commit = fetch_git(repo, rev.as_deref(), staging)?;
+            (staging.to_owned(), Source::Git(repo.clone(), Some(commit)))
+        }
+
+        Source::Tarball(tarball) => (fetch_tarball(tarball, staging)?, source.clone()),
+    };
+
+    let manifest: Manifest = manifest::read(&dir.join(MANIFEST))?;
+    let Some(package) = manifest.package.clone() else {
+        return Err(format!(
+            "package from {source} has no [package] section in its {MANIFEST}"
+        ));
+    };
+
+    let hash = hash_dir(&dir)?;
+
+    Ok(Fetched {
+        dir,
+        package,
+        manifest,
+        source,
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkg::resolve;
+
+    #[test]
+    fn links_are_kept_as_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("lib");
+
+        resolve::tests::package(&lib, "lib", "1.0.0", &[]);
+        std::os::unix::fs::symlink(".", lib.join("loop")).unwrap();
+        std::os::unix::fs::symlink("init.luau", lib.join("main.luau")).unwrap();
+
+        let staging = dir.path().join("staging");
+        let fetched = fetch(&Source::Path(lib.clone()), &staging).unwrap();
+
+        assert_eq!(
+            std::fs::read_link(staging.join("loop")).unwrap(),
+            Path::new(".")
+        );
+        assert_eq!(
+            std::fs::read_link(staging.join("main.luau")).unwrap(),
+            Path::new("init.luau")
+        );
+        assert_eq!(fetched.hash, hash_dir(&lib).unwrap());
+
+        // retargeting a link changes the hash
+        std::fs::remove_file(lib.join("main.luau")).unwrap();
+        std::os::unix::fs::symlink(MANIFEST, lib.join("main.luau")).unwrap();
+        assert_ne!(fetched.hash, hash_dir(&lib).unwrap());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::luaurc::normalize;
+
+pub const MANIFEST: &str = "bre.toml";
+pub const LOCKFILE: &str = "bre.lock";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<Package>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, Dependency>,
+
+    #[serde(default, skip_serializing_if = "Require::is_empty")]
+    pub require: Require,
+
+    /// Shared libraries loaded by `@plugin/name` requires.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Require {
+    /// Extra directories searched for `@name` requires.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathBuf>,
+
+    /// Whether `.lua` and `init.lua` files can be required.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lua: bool,
+}
+
+impl Require {
+    fn is_empty(&self) -> bool {
+        self.paths.is_empty() && !self.lua
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: Version,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball: Option<PathBuf>,
+
+    #[serde(default)]
+    pub version: VersionReq,
+}
+
+impl Dependency {
+    /// Resolves where this dependency comes from, with relative paths taken
+    /// from `base`, the directory of the manifest that declares it.
+    pub fn source(&self, name: &str, base: &Path) -> Result<Source, String> {
+        let abs = |path: &PathBuf| normalize(&base.join(path));
+
+        match (&self.path, &self.git, &self.tarball) {
+            (Some(path), None, None) => Ok(Source::Path(abs(path))),
+            (None, Some(git), None) => Ok(Source::Git(abs(git), self.rev.clone())),
+            (None, None, Some(tarball)) => Ok(Source::Tarball(abs(tarball))),
+            _ => Err(format!(
+                "dependency '{name}' must specify exactly one of `path`, `git` or `tarball`"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    Path(PathBuf),
+    Git(PathBuf, Option<String>),
+    Tarball(PathBuf),
+}
+
+impl Source {
+    /// The directory that relative paths in the package's own manifest are
+    /// resolved against.
+    pub fn origin(&self) -> PathBuf {
+        match self {
+            Source::Path(path) | Source::Git(path, _) => path.clone(),
+            Source::Tarball(path) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        }
+    }
+
+    pub fn relative_to(&self, base: &Path) -> Source {
+        match self {
+            Source::Path(path) => Source::Path(relative(path, base)),
+            Source::Git(path, rev) => Source::Git(relative(path, base), rev.clone()),
+            Source::Tarball(path) => Source::Tarball(relative(path, base)),
+        }
+    }
+
+    pub fn absolute(&self, base: &Path) -> Source {
+        match self {
+            Source::Path(path) => Source::Path(normalize(&base.join(path))),
+            Source::Git(path, rev) => Source::Git(normalize(&base.join(path)), rev.clone()),
+            Source::Tarball(path) => Source::Tarball(normalize(&base.join(path))),
+        }
+    }
+
+    pub fn same_location(&self, other: &Source) -> bool {
+        match (self, other) {
+            (Source::Path(a), Source::Path(b)) => a == b,
+            (Source::Git(a, _), Source::Git(b, _)) => a == b,
+            (Source::Tarball(a), Source::Tarball(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Path(path) => write!(f, "path+{}", path.display()),
+            Source::Git(path, None) => write!(f, "git+{}", path.display()),
+            Source::Git(path, Some(rev)) => write!(f, "git+{}#{rev}", path.display()),
+            Source::Tarball(path) => write!(f, "tarball+{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('+') {
+            Some(("path", path)) => Ok(Source::Path(path.into())),
+            Some(("tarball", path)) => Ok(Source::Tarball(path.into())),
+            Some(("git", rest)) => match rest.rsplit_once('#') {
+                Some((path, rev)) => Ok(Source::Git(path.into(), Some(rev.to_owned()))),
+                None => Ok(Source::Git(rest.into(), None)),
+            },
+
+            _ => Err(format!("invalid package source '{s}'")),
+        }
+    }
+}
+
+impl Serialize for Source {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Source {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Version,
+    pub source: Source,
+    pub hash: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+pub fn read<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> Result<T, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents)
+            .map_err(|e| format!("failed to parse '{}': {e}", path.display())),
+
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("failed to read '{}': {e}", path.display())),
+    }
+}
+
+pub fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let contents = toml::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| format!("failed to write '{}': {e}", path.display()))
+}
+
+pub fn relative(path: &Path, base: &Path) -> PathBuf {
+    let path = normalize(path);
+    let base = normalize(base);
+
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut out = PathBuf::new();
+    for _ in base.components().skip(common) {
+        out.push("..");
+    }
+
+    out.extend(path.components().skip(common));
+
+    match out.components().next() {
+        Some(Component::ParentDir) => out,
+        _ => Path::new(".").join(out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_round_trip() {
+        for source in [
+            Source::Path("./deps/a".into()),
+            Source::Git("../repo".into(), None),
+            Source::Git("../repo".into(), Some("abc123".into())),
+            Source::Tarball("pkg.tar.gz".into()),
+        ] {
+            assert_eq!(source.to_string().parse::<Source>(), Ok(source));
+        }
+
+        assert!("svn+repo".parse::<Source>().is_err());
+    }
+
+    #[test]
+    fn lockfile_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE);
+
+        let lockfile = Lockfile {
+            packages: vec![
+                LockedPackage {
+                    name: "a".into(),
+                    version: Version::new(1, 2, 3),
+                    source: Source::Git("../a".into(), Some("abc123".into())),
+                    hash: "sha256-00".into(),
+                    dependencies: vec!["b".into()],
+                },
+                LockedPackage {
+                    name: "b".into(),
+                    version: Version::parse("0.1.0-beta.1").unwrap(),
+                    source: Source::Path("./b".into()),
+                    hash: "sha256-11".into(),
+                    dependencies: Vec::new(),
+                },
+            ],
+        };
+
+        write(&path, &lockfile).unwrap();
+        let read: Lockfile = read(&path).unwrap();
+
+        assert_eq!(format!("{read:?}"), format!("{lockfile:?}"));
+    }
+
+    #[test]
+    fn missing_file_is_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile: Lockfile = read(&dir.path().join(LOCKFILE)).unwrap();
+
+        assert!(lockfile.packages.is_empty());
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(
+            relative(Path::new("/a/b/c"), Path::new("/a")),
+            Path::new("./b/c")
+        );
+        assert_eq!(
+            relative(Path::new("/a/c"), Path::new("/a/b")),
+            Path::new("../c")
+        );
+    }
+}
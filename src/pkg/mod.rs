@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+
+use semver::VersionReq;
+
+mod fetch;
+pub mod manifest;
+mod resolve;
+
+use fetch::Fetched;
+use manifest::{Dependency, LOCKFILE, LockedPackage, Lockfile, MANIFEST, Manifest};
+
+const PACKAGES: &str = "packages";
+const ALIAS: &str = "pkg";
+
+fn lock_matches(manifest: &Manifest, lockfile: &Lockfile, project: &Path) -> Result<bool, String> {
+    for (name, dep) in &manifest.dependencies {
+        let source = dep.source(name, project)?;
+
+        let locked = lockfile.packages.iter().any(|locked| {
+            &locked.name == name
+                && dep.version.matches(&locked.version)
+                && locked.source.absolute(project).same_location(&source)
+        });
+
+        if !locked {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn fetch_locked(
+    lockfile: &Lockfile,
+    project: &Path,
+    staging: &Path,
+) -> Result<Vec<Fetched>, String> {
+    let mut packages = Vec::new();
+
+    for (i, locked) in lockfile.packages.iter().enumerate() {
+        let source = locked.source.absolute(project);
+        let fetched = fetch::fetch(&source, &staging.join(i.to_string()))?;
+
+        if fetched.package.name != locked.name || fetched.package.version != locked.version {
+            return Err(format!(
+                "{source} now contains {}@{}, but {LOCKFILE} expects {}@{}; run `bre install --update` to accept the change",
+                fetched.package.name, fetched.package.version, locked.name, locked.version
+            ));
+        }
+
+        if fetched.hash != locked.hash {
+            return Err(format!(
+                "hash mismatch for '{}': {LOCKFILE} has {}, but {source} has {}; run `bre install --update` to accept the change",
+                locked.name, locked.hash, fetched.hash
+            ));
+        }
+
+        packages.push(fetched);
+    }
+
+    Ok(packages)
+}
+
+fn resolve_and_lock(
+    manifest: &Manifest,
+    project: &Path,
+    staging: &Path,
+) -> Result<Vec<Fetched>, String> {
+    let resolved = resolve::resolve(manifest, project, staging)?;
+
+    let mut packages: Vec<LockedPackage> = resolved
+        .packages
+        .iter()
+        .map(|f| LockedPackage {
+            name: f.package.name.clone(),
+            version: f.package.version.clone(),
+            source: f.source.relative_to(project),
+            hash: f.hash.clone(),
+            dependencies: resolved.dependencies[&f.package.name].clone(),
+        })
+        .collect();
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    manifest::write(&project.join(LOCKFILE), &Lockfile { packages })?;
+
+    Ok(resolved.packages)
+}
+
+fn place(packages: &[Fetched], project: &Path) -> Result<(), String> {
+    let dir = project.join(PACKAGES);
+
+    for package in packages {
+        let target = dir.join(&package.package.name);
+
+        if target.exists() {
+            std::fs::remove_dir_all(&target)
+                .map_err(|e| format!("failed to remove '{}': {e}", target.display()))?;
+        }
+
+        std::fs::rename(&package.dir, &target)
+            .map_err(|e| format!("failed to install '{}': {e}", target.display()))?;
+
+        println!(
+            "installed {}@{}",
+            package.package.name, package.package.version
+        );
+    }
+
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(());
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let stale = !name.to_string_lossy().starts_with('.')
+            && !packages.iter().any(|p| *p.package.name == *name);
+
+        if stale && entry.path().is_dir() {
+            std::fs::remove_dir_all(entry.path())
+                .map_err(|e| format!("failed to remove '{}': {e}", entry.path().display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_alias(project: &Path) -> Result<(), String> {
+    use serde_json::{Value, json};
+
+    let path = project.join(".luaurc");
+    let mut config: Value = match std::fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map_err(|e| format!("failed to parse '{}': {e}", path.display()))?,
+
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+        Err(e) => return Err(format!("failed to read '{}': {e}", path.display())),
+    };
+
+    let target = Value::String(format!("./{PACKAGES}"));
+    let aliases = config
+        .as_object_mut()
+        .ok_or_else(|| format!("'{}' is not a json object", path.display()))?
+        .entry("aliases")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .ok_or_else(|| format!("'aliases' in '{}' is not a json object", path.display()))?;
+
+    if aliases.get(ALIAS) == Some(&target) {
+        return Ok(());
+    }
+
+    aliases.insert(ALIAS.to_owned(), target);
+
+    let contents = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("failed to write '{}': {e}", path.display()))
+}
+
+fn with_staging<T>(
+    project: &Path,
+    f: impl FnOnce(&Path) -> Result<T, String>,
+) -> Result<T, String> {
+    let staging = project.join(PACKAGES).join(".staging");
+    let _ = std::fs::remove_dir_all(&staging);
+
+    let result = f(&staging);
+
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn canonical_project(project: &Path) -> Result<PathBuf, String> {
+    project
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve '{}': {e}", project.display()))
+}
+
+fn install_manifest(manifest: &Manifest, project: &Path, update: bool) -> Result<(), String> {
+    let lockfile: Lockfile = manifest::read(&project.join(LOCKFILE))?;
+
+    with_staging(project, |staging| {
+        let packages = if !update && lock_matches(manifest, &lockfile, project)? {
+            fetch_locked(&lockfile, project, staging)?
+        } else {
+            resolve_and_lock(manifest, project, staging)?
+        };
+
+        place(&packages, project)
+    })?;
+
+    write_alias(project)
+}
+
+/// Installs the dependencies of the project in `project` into its packages
+/// directory, using the lockfile unless it is out of date or `update` is set.
+pub fn install(project: &Path, update: bool) -> Result<(), String> {
+    let project = canonical_project(project)?;
+    let manifest: Manifest = manifest::read(&project.join(MANIFEST))?;
+
+    install_manifest(&manifest, &project, update)
+}
+
+fn classify(source: &str, rev: Option<String>) -> Result<Dependency, String> {
+    let mut dep = Dependency {
+        path: None,
+        git: None,
+        rev: None,
+        tarball: None,
+        version: VersionReq::default(),
+    };
+
+    let path = Path::new(source.strip_prefix("git+").unwrap_or(source));
+    let is_tarball = [".tar", ".tar.gz", ".tgz", ".tar.xz", ".tar.bz2", ".tar.zst"]
+        .iter()
+        .any(|ext| source.ends_with(ext));
+
+    if source.starts_with("git+") || path.join(".git").exists() || source.ends_with(".git") {
+        dep.git = Some(path.to_owned());
+        dep.rev = rev;
+        return Ok(dep);
+    }
+
+    if rev.is_some() {
+        return Err("`--rev` can only be used with git dependencies".to_owned());
+    }
+
+    if is_tarball && path.is_file() {
+        dep.tarball = Some(path.to_owned());
+    } else if path.is_dir() {
+        dep.path = Some(path.to_owned());
+    } else {
+        return Err(format!(
+            "'{source}' is not a directory, git repository or tarball"
+        ));
+    }
+
+    Ok(dep)
+}
+
+/// Adds a dependency to the manifest of the project in `project` under the
+/// package's own name and installs it, leaving the manifest untouched if
+/// installation fails.
+pub fn add(
+    project: &Path,
+    source: &str,
+    version: Option<VersionReq>,
+    rev: Option<String>,
+) -> Result<(), String> {
+    let project = canonical_project(project)?;
+    let mut dep = classify(source, rev)?;
+    let package = with_staging(&project, |staging| {
+        fetch::fetch(&dep.source(source, Path::new("."))?, staging).map(|f| f.package)
+    })?;
+
+    dep.version = match version {
+        Some(version) => version,
+        None => VersionReq::parse(&format!("^{}", package.version)).map_err(|e| e.to_string())?,
+    };
+
+    let mut manifest: Manifest = manifest::read(&project.join(MANIFEST))?;
+    manifest.dependencies.insert(package.name, dep);
+
+    install_manifest(&manifest, &project, false)?;
+    manifest::write(&project.join(MANIFEST), &manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use manifest::Source;
+
+    #[test]
+    fn add_installs_and_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        resolve::tests::package(&root.join("lib"), "lib", "1.4.0", &[]);
+        std::fs::create_dir(root.join("app")).unwrap();
+
+        let source = root.join("lib");
+        add(&root.join("app"), source.to_str().unwrap(), None, None).unwrap();
+
+        let manifest: Manifest = manifest::read(&root.join("app").join(MANIFEST)).unwrap();
+        assert_eq!(manifest.dependencies["lib"].version.to_string(), "^1.4.0");
+
+        let lockfile: Lockfile = manifest::read(&root.join("app").join(LOCKFILE)).unwrap();
+        assert_eq!(lockfile.packages.len(), 1);
+        assert_eq!(lockfile.packages[0].name, "lib");
+        assert_eq!(lockfile.packages[0].source, Source::Path("../lib".into()));
+
+        assert!(root.join("app/packages/lib/init.luau").is_file());
+        assert!(!root.join("app/packages/.staging").exists());
+
+        // the lockfile is reused as long as it still matches
+        install(&root.join("app"), false).unwrap();
+        let relocked: Lockfile = manifest::read(&root.join("app").join(LOCKFILE)).unwrap();
+        assert_eq!(relocked.packages[0].hash, lockfile.packages[0].hash);
+    }
+}
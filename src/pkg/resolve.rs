@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use semver::VersionReq;
+
+use super::{
+    fetch::{self, Fetched},
+    manifest::{Dependency, Manifest, Source},
+};
+
+struct Requirement {
+    requirer: String,
+    version: VersionReq,
+    source: Source,
+}
+
+pub struct Resolved {
+    pub packages: Vec<Fetched>,
+    pub dependencies: HashMap<String, Vec<String>>,
+}
+
+/// Fetches every package reachable from `manifest` and picks one version of
+/// each, reporting every requirement involved when no version satisfies them
+/// all.
+pub fn resolve(manifest: &Manifest, project: &Path, staging: &Path) -> Result<Resolved, String> {
+    let mut queue: VecDeque<(String, String, Dependency, PathBuf)> = manifest
+        .dependencies
+        .iter()
+        .map(|(name, dep)| {
+            (
+                "the project".to_owned(),
+                name.clone(),
+                dep.clone(),
+                project.to_owned(),
+            )
+        })
+        .collect();
+
+    let mut fetched: Vec<Fetched> = Vec::new();
+    let mut by_source: HashMap<Source, usize> = HashMap::new();
+    let mut requirements: BTreeMap<String, Vec<Requirement>> = BTreeMap::new();
+
+    while let Some((requirer, name, dep, base)) = queue.pop_front() {
+        let source = dep.source(&name, &base)?;
+
+        let index = match by_source.get(&source) {
+            Some(&index) => index,
+            None => {
+                let package = fetch::fetch(&source, &staging.join(fetched.len().to_string()))?;
+
+                if package.package.name != name {
+                    return Err(format!(
+                        "{requirer} depends on '{name}', but {source} contains package '{}'",
+                        package.package.name
+                    ));
+                }
+
+                let label = format!("{name}@{}", package.package.version);
+                let origin = source.origin();
+
+                for (dep_name, dep) in &package.manifest.dependencies {
+                    queue.push_back((label.clone(), dep_name.clone(), dep.clone(), origin.clone()));
+                }
+
+                by_source.insert(source.clone(), fetched.len());
+                fetched.push(package);
+                fetched.len() - 1
+            }
+        };
+
+        requirements.entry(name).or_default().push(Requirement {
+            requirer,
+            version: dep.version.clone(),
+            source: fetched[index].source.clone(),
+        });
+    }
+
+    let mut chosen = Vec::new();
+    let mut errors = String::new();
+
+    for (name, reqs) in &requirements {
+        let best = fetched
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| &f.package.name == name)
+            .filter(|(_, f)| reqs.iter().all(|r| r.version.matches(&f.package.version)))
+            .max_by(|(_, a), (_, b)| a.package.version.cmp(&b.package.version));
+
+        match best {
+            Some((index, _)) => chosen.push(index),
+            None => {
+                let _ = writeln!(
+                    errors,
+                    "no version of '{name}' satisfies every requirement:"
+                );
+
+                for req in reqs {
+                    let provides = fetched
+                        .iter()
+                        .find(|f| f.source == req.source)
+                        .map(|f| f.package.version.to_string())
+                        .unwrap_or_default();
+
+                    let _ = writeln!(
+                        errors,
+                        "  {} requires {} {} (from {}, which provides {provides})",
+                        req.requirer, name, req.version, req.source,
+                    );
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.trim_end().to_owned());
+    }
+
+    let dependencies = chosen
+        .iter()
+        .map(|&i| {
+            let f = &fetched[i];
+            (
+                f.package.name.clone(),
+                f.manifest.dependencies.keys().cloned().collect(),
+            )
+        })
+        .collect();
+
+    let mut chosen_flags = vec![false; fetched.len()];
+    for i in chosen {
+        chosen_flags[i] = true;
+    }
+
+    let packages = fetched
+        .into_iter()
+        .zip(chosen_flags)
+        .filter_map(|(f, keep)| keep.then_some(f))
+        .collect();
+
+    Ok(Resolved {
+        packages,
+        dependencies,
+    })
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Writes a package manifest to `dir`, depending on `(name, path, version)`
+    /// triples with paths relative to `dir`.
+    pub fn package(dir: &Path, name: &str, version: &str, deps: &[(&str, &str, &str)]) {
+        std::fs::create_dir_all(dir).unwrap();
+
+        let mut manifest = format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n");
+        manifest.push_str("\n[dependencies]\n");
+
+        for (name, path, version) in deps {
+            manifest.push_str(&format!(
+                "{name} = {{ path = \"{path}\", version = \"{version}\" }}\n"
+            ));
+        }
+
+        std::fs::write(dir.join("bre.toml"), manifest).unwrap();
+        std::fs::write(dir.join("init.luau"), format!("return \"{name}\"")).unwrap();
+    }
+
+    fn project(dir: &Path, deps: &[(&str, &str, &str)]) -> Manifest {
+        package(dir, "project", "0.1.0", deps);
+        super::super::manifest::read(&dir.join("bre.toml")).unwrap()
+    }
+
+    fn versions(resolved: &Resolved) -> Vec<String> {
+        let mut versions: Vec<_> = resolved
+            .packages
+            .iter()
+            .map(|f| format!("{}@{}", f.package.name, f.package.version))
+            .collect();
+
+        versions.sort();
+        versions
+    }
+
+    #[test]
+    fn transitive() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        package(&root.join("a"), "a", "1.0.0", &[("b", "../b", "^2")]);
+        package(&root.join("b"), "b", "2.3.0", &[]);
+        let manifest = project(&root.join("app"), &[("a", "../a", "^1")]);
+
+        let resolved = resolve(&manifest, &root.join("app"), &root.join("staging")).unwrap();
+
+        assert_eq!(versions(&resolved), ["a@1.0.0", "b@2.3.0"]);
+        assert_eq!(resolved.dependencies["a"], ["b"]);
+        assert!(resolved.dependencies["b"].is_empty());
+    }
+
+    #[test]
+    fn picks_highest_matching_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        package(&root.join("a1"), "a", "1.0.0", &[]);
+        package(&root.join("a2"), "a", "1.2.0", &[]);
+        package(&root.join("b"), "b", "1.0.0", &[("a", "../a2", "^1.1")]);
+        let manifest = project(
+            &root.join("app"),
+            &[("a", "../a1", "^1"), ("b", "../b", "*")],
+        );
+
+        let resolved = resolve(&manifest, &root.join("app"), &root.join("staging")).unwrap();
+
+        assert_eq!(versions(&resolved), ["a@1.2.0", "b@1.0.0"]);
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        package(&root.join("a1"), "a", "1.0.0", &[]);
+        package(&root.join("a2"), "a", "2.0.0", &[]);
+        package(&root.join("b"), "b", "1.0.0", &[("a", "../a2", "^2")]);
+        let manifest = project(
+            &root.join("app"),
+            &[("a", "../a1", "^1"), ("b", "../b", "*")],
+        );
+
+        let Err(err) = resolve(&manifest, &root.join("app"), &root.join("staging")) else {
+            panic!("conflicting requirements resolved");
+        };
+
+        assert!(
+            err.starts_with("no version of 'a' satisfies every requirement:"),
+            "{err}"
+        );
+        assert!(err.contains("the project requires a ^1"), "{err}");
+        assert!(err.contains("b@1.0.0 requires a ^2"), "{err}");
+        assert!(err.contains("which provides 2.0.0"), "{err}");
+    }
+
+    #[test]
+    fn rejects_mismatched_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        package(&root.join("a"), "other", "1.0.0", &[]);
+        let manifest = project(&root.join("app"), &[("a", "../a", "*")]);
+
+        let Err(err) = resolve(&manifest, &root.join("app"), &root.join("staging")) else {
+            panic!("mismatched name resolved");
+        };
+
+        assert!(err.contains("contains package 'other'"), "{err}");
+    }
+}
@@ -1,14 +1,161 @@
-use std::path::Path;
+use std::{
+    ffi::OsString,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
 use super::util::unblock;
 
 pub async fn read<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "io-uring")]
+    if super::uring::supported() {
+        return super::uring::read(path.as_ref()).await;
+    }
+
     let path = path.as_ref().to_owned();
     unblock(move || std::fs::read(path)).await
 }
 
 pub async fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> std::io::Result<()> {
+    #[cfg(feature = "io-uring")]
+    if super::uring::supported() {
+        return super::uring::write(path.as_ref(), contents.as_ref().to_owned()).await;
+    }
+
     let path = path.as_ref().to_owned();
     let contents = contents.as_ref().to_owned();
     unblock(move || std::fs::write(path, contents)).await
 }
+
+pub async fn metadata<P: AsRef<Path>>(path: P) -> std::io::Result<std::fs::Metadata> {
+    let path = path.as_ref().to_owned();
+    unblock(move || std::fs::symlink_metadata(path)).await
+}
+
+pub async fn exists<P: AsRef<Path>>(path: P) -> std::io::Result<bool> {
+    let path = path.as_ref().to_owned();
+    unblock(move || path.try_exists()).await
+}
+
+pub async fn read_dir<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<OsString>> {
+    let path = path.as_ref().to_owned();
+    unblock(move || {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect()
+    })
+    .await
+}
+
+pub async fn create_dir<P: AsRef<Path>>(path: P, recursive: bool) -> std::io::Result<()> {
+    let path = path.as_ref().to_owned();
+    unblock(move || match recursive {
+        true => std::fs::create_dir_all(path),
+        false => std::fs::create_dir(path),
+    })
+    .await
+}
+
+pub async fn remove_file<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    let path = path.as_ref().to_owned();
+    unblock(move || std::fs::remove_file(path)).await
+}
+
+pub async fn remove_dir<P: AsRef<Path>>(path: P, recursive: bool) -> std::io::Result<()> {
+    let path = path.as_ref().to_owned();
+    unblock(move || match recursive {
+        true => std::fs::remove_dir_all(path),
+        false => std::fs::remove_dir(path),
+    })
+    .await
+}
+
+pub async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> std::io::Result<()> {
+    let (from, to) = (from.as_ref().to_owned(), to.as_ref().to_owned());
+    unblock(move || std::fs::rename(from, to)).await
+}
+
+/// Copies a file, or a directory and everything in it.
+pub async fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> std::io::Result<()> {
+    fn copy_all(from: &Path, to: &Path) -> std::io::Result<()> {
+        if !std::fs::symlink_metadata(from)?.is_dir() {
+            return std::fs::copy(from, to).map(drop);
+        }
+
+        std::fs::create_dir(to)?;
+
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+
+        Ok(())
+    }
+
+    let (from, to) = (from.as_ref().to_owned(), to.as_ref().to_owned());
+    unblock(move || copy_all(&from, &to)).await
+}
+
+pub async fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(target: P, link: Q) -> std::io::Result<()> {
+    let (target, link) = (target.as_ref().to_owned(), link.as_ref().to_owned());
+    unblock(move || std::os::unix::fs::symlink(target, link)).await
+}
+
+pub async fn read_link<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf> {
+    let path = path.as_ref().to_owned();
+    unblock(move || std::fs::read_link(path)).await
+}
+
+pub async fn set_permissions<P: AsRef<Path>>(path: P, mode: u32) -> std::io::Result<()> {
+    let path = path.as_ref().to_owned();
+    unblock(move || std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))).await
+}
+
+/// Creates an empty file in the system temp directory that is not removed
+/// automatically.
+pub async fn temp_file(prefix: Option<OsString>) -> std::io::Result<PathBuf> {
+    unblock(move || {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(prefix.as_deref().unwrap_or("bre".as_ref()));
+
+        Ok(builder.tempfile()?.keep()?.1)
+    })
+    .await
+}
+
+/// Creates a directory in the system temp directory that is not removed
+/// automatically.
+pub async fn temp_dir(prefix: Option<OsString>) -> std::io::Result<PathBuf> {
+    unblock(move || {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(prefix.as_deref().unwrap_or("bre".as_ref()));
+
+        Ok(builder.tempdir()?.keep())
+    })
+    .await
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so
+/// readers see either the old contents or the new ones.
+pub async fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    contents: C,
+) -> std::io::Result<()> {
+    let path = path.as_ref().to_owned();
+    let contents = contents.as_ref().to_owned();
+    unblock(move || {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(&contents)?;
+        file.as_file().sync_all()?;
+        file.persist(&path)?;
+
+        Ok(())
+    })
+    .await
+}
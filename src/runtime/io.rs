@@ -0,0 +1,193 @@
+use std::{
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, RawFd},
+};
+
+pub use super::reactor::Interest;
+use super::reactor::{self, Registration};
+
+/// Wraps a file descriptor so its reads and writes wait on the reactor
+/// instead of blocking a thread.
+pub struct Async<T: AsRawFd> {
+    // dropped first, so the descriptor is deregistered before it closes
+    registration: Registration,
+    io: T,
+}
+
+fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+
+    if flags == -1 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+impl<T: AsRawFd> Async<T> {
+    /// Switches `io` to non-blocking mode and registers it with the reactor.
+    pub fn new(io: T) -> io::Result<Self> {
+        let fd = io.as_raw_fd();
+
+        set_nonblocking(fd)?;
+        let registration = reactor::register(fd)?;
+
+        Ok(Self { registration, io })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Runs `op` whenever the descriptor is ready for `interest`, until it
+    /// stops failing with `WouldBlock`.
+    pub async fn io_with<R>(
+        &self,
+        interest: Interest,
+        mut op: impl FnMut(&T) -> io::Result<R>,
+    ) -> io::Result<R> {
+        loop {
+            let tick = self.registration.ready(interest).await?;
+
+            match op(&self.io) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    self.registration.clear(interest, tick);
+                }
+
+                result => return result,
+            }
+        }
+    }
+
+    pub async fn readable(&self) -> io::Result<()> {
+        self.registration.ready(Interest::Readable).await.map(drop)
+    }
+
+    pub async fn writable(&self) -> io::Result<()> {
+        self.registration.ready(Interest::Writable).await.map(drop)
+    }
+}
+
+impl<T: AsRawFd> Async<T>
+where
+    for<'a> &'a T: Read,
+{
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io_with(Interest::Readable, |mut io| io.read(buf))
+            .await
+    }
+}
+
+impl<T: AsRawFd> Async<T>
+where
+    for<'a> &'a T: Write,
+{
+    pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.io_with(Interest::Writable, |mut io| io.write(buf))
+            .await
+    }
+
+    pub async fn write_all(&self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf).await? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                n => buf = &buf[n..],
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell, io::ErrorKind, os::unix::net::UnixStream, rc::Rc, thread, time::Duration,
+    };
+
+    use super::*;
+    use crate::runtime::Executor;
+
+    fn block_on<T: 'static>(future: impl Future<Output = T> + 'static) -> T {
+        let executor = Executor::default();
+        let output = Rc::new(RefCell::new(None));
+
+        let slot = output.clone();
+        executor.spawner().spawn(async move {
+            *slot.borrow_mut() = Some(future.await);
+        });
+        executor.run();
+
+        output.take().unwrap()
+    }
+
+    #[test]
+    fn read_waits_for_data() {
+        let (a, mut b) = UnixStream::pair().unwrap();
+
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            b.write_all(b"hello").unwrap();
+        });
+
+        let data = block_on(async move {
+            let a = Async::new(a).unwrap();
+            let mut buf = [0; 16];
+            let n = a.read(&mut buf).await.unwrap();
+            buf[..n].to_vec()
+        });
+
+        assert_eq!(data, b"hello");
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn write_waits_for_space() {
+        let (a, mut b) = UnixStream::pair().unwrap();
+        let a = Async::new(a).unwrap();
+
+        // fill the socket buffer
+        let mut sent = 0;
+        loop {
+            match a.get_ref().write(&[0; 4096]) {
+                Ok(n) => sent += n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => panic!("{e}"),
+            }
+        }
+
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+
+            let mut data = Vec::new();
+            b.read_to_end(&mut data).unwrap();
+            data.len()
+        });
+
+        block_on(async move {
+            a.write_all(b"more").await.unwrap();
+        });
+
+        assert_eq!(reader.join().unwrap(), sent + 4);
+    }
+
+    #[test]
+    fn writable_right_away() {
+        let (a, _b) = UnixStream::pair().unwrap();
+
+        block_on(async move {
+            Async::new(a).unwrap().writable().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn deregisters_on_drop() {
+        let (a, _b) = UnixStream::pair().unwrap();
+
+        let registration = reactor::register(a.as_raw_fd()).unwrap();
+        assert!(reactor::register(a.as_raw_fd()).is_err());
+
+        drop(registration);
+        reactor::register(a.as_raw_fd()).unwrap();
+    }
+}
@@ -1,116 +1,289 @@
 use std::{
     cell::{Cell, RefCell},
+    collections::VecDeque,
     pin::Pin,
-    sync::Arc,
-    task::{Context, Poll, Wake, Waker},
+    sync::{
+        Arc, Once, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Wake, Waker},
 };
 
-use crossbeam::channel::{self, Receiver, Sender};
+use crossbeam::{
+    queue::SegQueue,
+    sync::{Parker, Unparker},
+};
 
 mod reactor;
+mod wheel;
 
 pub mod fs;
+pub mod io;
 pub mod time;
+#[cfg(feature = "io-uring")]
+pub mod uring;
 pub mod util;
+pub mod walk;
+pub mod watch;
+
+/// How many tasks are taken from the local queue before the wake queue gets
+/// a turn, so a busy script can't starve completions from other threads.
+const REMOTE_INTERVAL: u32 = 32;
+
+/// Wakes arriving from other threads, like the reactor and rayon.
+struct Remote {
+    queue: SegQueue<Arc<Handle>>,
+    unparker: Unparker,
+}
+
+/// The part of a task shared with its waker.
+struct Handle {
+    key: usize,
+    remote: Weak<Remote>,
+
+    /// Set while the task sits in a queue, so repeated wakes queue it once.
+    scheduled: AtomicBool,
+}
+
+type LocalQueue = RefCell<VecDeque<Arc<Handle>>>;
+
+thread_local! {
+    /// The executor polling on this thread, letting same-thread wakes skip
+    /// the wake queue.
+    static CURRENT: Cell<Option<(*const Remote, *const LocalQueue)>> = const { Cell::new(None) };
+}
+
+impl Wake for Handle {
+    fn wake(self: Arc<Self>) {
+        if self.scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let local = CURRENT.get().and_then(|(remote, local)| {
+            std::ptr::eq(remote, self.remote.as_ptr()).then_some(local)
+        });
+
+        match local {
+            // SAFETY: `CURRENT` only points at an executor while it is polling
+            Some(local) => unsafe { &*local }.borrow_mut().push_back(self),
+
+            None => {
+                if let Some(remote) = self.remote.upgrade() {
+                    remote.queue.push(self);
+                    remote.unparker.unpark();
+                }
+            }
+        }
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.clone().wake();
+    }
+}
+
+struct Task {
+    /// Taken out while the task is being polled.
+    future: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    handle: Arc<Handle>,
+    waker: Waker,
+
+    /// Whether the task keeps `Executor::run` from returning.
+    referenced: bool,
+}
 
+/// Identifies a spawned task, to change whether it keeps the executor
+/// running.
+#[derive(Clone)]
+pub struct TaskId(Arc<Handle>);
+
+/// A single-threaded executor. Tasks live in a slab owned by the executor
+/// and are never shared with other threads; only their wakers are.
 pub struct Executor {
-    queue: Receiver<Arc<Task>>,
-    sender: Sender<Arc<Task>>,
+    tasks: RefCell<Vec<Option<Task>>>,
+    free: RefCell<Vec<usize>>,
+
+    local: LocalQueue,
+    remote: Arc<Remote>,
+    parker: Parker,
+
+    /// Referenced tasks that haven't finished.
     pending: Cell<usize>,
+    keep_alive: Cell<bool>,
 }
 
 impl Default for Executor {
     fn default() -> Self {
-        std::thread::spawn(reactor::reactor);
+        static REACTOR: Once = Once::new();
+        REACTOR.call_once(|| {
+            std::thread::spawn(reactor::reactor);
+        });
 
-        let (tx, rx) = channel::unbounded();
+        let parker = Parker::new();
 
         Self {
-            queue: rx,
-            sender: tx,
+            tasks: RefCell::default(),
+            free: RefCell::default(),
+            local: RefCell::default(),
+            remote: Arc::new(Remote {
+                queue: SegQueue::new(),
+                unparker: parker.unparker().clone(),
+            }),
+            parker,
             pending: Cell::new(0),
+            keep_alive: Cell::new(false),
         }
     }
 }
 
 impl Executor {
     pub fn spawner(&self) -> Spawner {
-        Spawner {
-            sender: self.sender.clone(),
-            pending: &self.pending,
-        }
+        Spawner { executor: self }
     }
 
     pub fn run(&self) {
-        if self.pending.get() == 0 {
-            return;
-        }
+        let mut tick = 0u32;
 
-        while let Ok(task) = self.queue.recv() {
-            if unsafe { task.poll() }.is_ready() {
-                self.pending.set(self.pending.get() - 1);
+        while self.pending.get() > 0 || self.keep_alive.get() {
+            tick = tick.wrapping_add(1);
 
-                if self.pending.get() == 0 {
-                    return;
-                }
+            let next = match tick % REMOTE_INTERVAL {
+                0 => self.remote.queue.pop(),
+                _ => None,
+            };
+
+            let next = next
+                .or_else(|| self.local.borrow_mut().pop_front())
+                .or_else(|| self.remote.queue.pop());
+
+            match next {
+                Some(handle) => self.poll(&handle),
+                None => self.parker.park(),
             }
         }
     }
-}
 
-#[derive(Clone)]
-pub struct Spawner<'executor> {
-    sender: Sender<Arc<Task>>,
-    pending: &'executor Cell<usize>,
-}
+    fn insert(&self, future: impl Future<Output = ()> + 'static) -> Arc<Handle> {
+        let mut tasks = self.tasks.borrow_mut();
+        let key = self.free.borrow_mut().pop().unwrap_or(tasks.len());
 
-impl Spawner<'_> {
-    pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
-        let task = Arc::new(Task::new(future, self.sender.clone()));
+        let handle = Arc::new(Handle {
+            key,
+            remote: Arc::downgrade(&self.remote),
+            scheduled: AtomicBool::new(false),
+        });
+
+        let task = Task {
+            future: Some(Box::pin(future)),
+            handle: handle.clone(),
+            waker: Waker::from(handle.clone()),
+            referenced: true,
+        };
 
-        if unsafe { task.poll() }.is_pending() {
-            self.pending.set(self.pending.get() + 1);
+        match tasks.get_mut(key) {
+            Some(slot) => *slot = Some(task),
+            None => tasks.push(Some(task)),
         }
+
+        self.pending.set(self.pending.get() + 1);
+        handle
     }
 
-    pub fn defer(&self, future: impl Future<Output = ()> + 'static) {
-        let task = Arc::new(Task::new(future, self.sender.clone()));
+    fn poll(&self, handle: &Arc<Handle>) {
+        let key = handle.key;
 
-        self.pending.set(self.pending.get() + 1);
-        self.sender.send(task).unwrap();
+        let (mut future, waker) = {
+            let mut tasks = self.tasks.borrow_mut();
+
+            // the task may have finished, and its slot been reused, since it
+            // was woken
+            let Some(task) = tasks[key]
+                .as_mut()
+                .filter(|t| Arc::ptr_eq(&t.handle, handle))
+            else {
+                return;
+            };
+
+            let Some(future) = task.future.take() else {
+                return;
+            };
+
+            (future, task.waker.clone())
+        };
+
+        handle.scheduled.store(false, Ordering::Release);
+
+        let previous = CURRENT.replace(Some((Arc::as_ptr(&self.remote), &self.local)));
+        let ready = future
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready();
+        CURRENT.set(previous);
+
+        let mut tasks = self.tasks.borrow_mut();
+
+        if ready {
+            if tasks[key].take().unwrap().referenced {
+                self.pending.set(self.pending.get() - 1);
+            }
+
+            self.free.borrow_mut().push(key);
+        } else {
+            tasks[key].as_mut().unwrap().future = Some(future);
+        }
     }
 }
 
-struct Task {
-    future: RefCell<Pin<Box<dyn Future<Output = ()> + 'static>>>,
-    sender: Sender<Arc<Self>>,
+#[derive(Clone)]
+pub struct Spawner<'executor> {
+    executor: &'executor Executor,
 }
 
-unsafe impl Send for Task {}
-unsafe impl Sync for Task {}
+impl Spawner<'_> {
+    /// Polls `future` right away, keeping it around if it isn't done.
+    pub fn spawn(&self, future: impl Future<Output = ()> + 'static) -> TaskId {
+        let handle = self.executor.insert(future);
+        self.executor.poll(&handle);
 
-impl Task {
-    pub fn new(future: impl Future<Output = ()> + 'static, sender: Sender<Arc<Self>>) -> Self {
-        Self {
-            future: RefCell::new(Box::pin(future)),
-            sender,
-        }
+        TaskId(handle)
     }
 
-    pub unsafe fn poll(self: Arc<Self>) -> Poll<()> {
-        let waker = Waker::from(Arc::clone(&self));
-        let cx = &mut Context::from_waker(&waker);
+    /// Queues `future` to be polled once the executor runs.
+    pub fn defer(&self, future: impl Future<Output = ()> + 'static) -> TaskId {
+        let handle = self.executor.insert(future);
 
-        self.future.borrow_mut().as_mut().poll(cx)
+        handle.scheduled.store(true, Ordering::Release);
+        self.executor.local.borrow_mut().push_back(handle.clone());
+
+        TaskId(handle)
     }
-}
 
-impl Wake for Task {
-    fn wake(self: Arc<Self>) {
-        self.sender.send(self.clone()).unwrap();
+    /// Sets whether the task keeps the executor running, like Node's
+    /// `ref` and `unref`. Tasks are referenced when spawned; unreferenced
+    /// ones still run, but are dropped if nothing else is left.
+    pub fn set_referenced(&self, task: &TaskId, referenced: bool) {
+        let mut tasks = self.executor.tasks.borrow_mut();
+
+        let Some(task) = tasks
+            .get_mut(task.0.key)
+            .and_then(Option::as_mut)
+            .filter(|t| Arc::ptr_eq(&t.handle, &task.0))
+        else {
+            return;
+        };
+
+        if task.referenced != referenced {
+            task.referenced = referenced;
+
+            let pending = self.executor.pending.get();
+            self.executor.pending.set(match referenced {
+                true => pending + 1,
+                false => pending - 1,
+            });
+        }
     }
 
-    fn wake_by_ref(self: &Arc<Self>) {
-        self.sender.send(self.clone()).unwrap();
+    /// Keeps the executor running with nothing pending, until turned off.
+    pub fn keep_alive(&self, keep_alive: bool) {
+        self.executor.keep_alive.set(keep_alive);
     }
 }
@@ -1,59 +1,322 @@
 use std::{
-    collections::BTreeMap,
-    sync::OnceLock,
-    task::Waker,
-    time::{Duration, Instant},
+    collections::HashMap,
+    ffi::c_int,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Instant,
 };
 
-use crossbeam::{
-    queue::SegQueue,
-    sync::{Parker, Unparker},
-};
+use crossbeam::queue::SegQueue;
 
-static UNPARKER: OnceLock<Unparker> = OnceLock::new();
+use super::wheel::Wheel;
 
-static TIMER: SegQueue<(Instant, Waker)> = SegQueue::new();
+/// The epoll instance every file descriptor is registered with.
+static EPOLL: LazyLock<OwnedFd> = LazyLock::new(|| {
+    let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    assert!(fd >= 0, "failed to create epoll instance");
 
-pub fn reactor() {
-    let parker = Parker::new();
+    unsafe { OwnedFd::from_raw_fd(fd) }
+});
 
-    UNPARKER
-        .set(parker.unparker().clone())
-        .expect("unparker already set");
+/// An eventfd written to whenever the reactor has new timers, waking it from
+/// `epoll_wait`.
+static WAKE: LazyLock<OwnedFd> = LazyLock::new(|| {
+    let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+    assert!(fd >= 0, "failed to create eventfd");
 
-    let mut timers = BTreeMap::new();
+    let wake = unsafe { OwnedFd::from_raw_fd(fd) };
+    add(wake.as_raw_fd(), WAKE_TOKEN).expect("failed to register eventfd");
 
-    loop {
-        let now = Instant::now();
+    wake
+});
+
+const WAKE_TOKEN: u64 = 0;
+
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(WAKE_TOKEN + 1);
+
+static SOURCES: LazyLock<Mutex<HashMap<u64, Source>>> = LazyLock::new(Default::default);
+
+static TIMER: SegQueue<Timer> = SegQueue::new();
+
+static NEXT_TIMER: AtomicU64 = AtomicU64::new(0);
+
+/// The instant the timer wheel counts milliseconds from.
+static START: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+enum Timer {
+    Add(u64, Instant, Waker),
+    Cancel(u64),
+}
+
+#[derive(Clone)]
+enum Source {
+    Io(Arc<Mutex<Readiness>>),
+
+    /// Called on the reactor thread whenever the descriptor becomes readable.
+    Callback(fn()),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    Readable,
+    Writable,
+}
+
+impl Interest {
+    fn events(self) -> u32 {
+        match self {
+            Interest::Readable => (libc::EPOLLIN | libc::EPOLLRDHUP) as u32,
+            Interest::Writable => libc::EPOLLOUT as u32,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Readiness {
+    readable: bool,
+    writable: bool,
+
+    /// The events the descriptor is registered for, added as futures ask for
+    /// them.
+    events: u32,
+
+    /// Bumped on every event, so a stale `WouldBlock` can't clear readiness
+    /// reported after the operation that hit it.
+    tick: u64,
+
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+/// A file descriptor registered with the reactor. Readiness is edge
+/// triggered: it stays set until an operation reports `WouldBlock` and clears
+/// it.
+pub struct Registration {
+    fd: RawFd,
+    token: u64,
+    readiness: Arc<Mutex<Readiness>>,
+}
+
+fn ctl(op: c_int, fd: RawFd, events: u32, token: u64) -> io::Result<()> {
+    let mut event = libc::epoll_event {
+        events: events | libc::EPOLLET as u32,
+        u64: token,
+    };
+
+    match unsafe { libc::epoll_ctl(EPOLL.as_raw_fd(), op, fd, &mut event) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+fn add(fd: RawFd, token: u64) -> io::Result<()> {
+    ctl(libc::EPOLL_CTL_ADD, fd, libc::EPOLLIN as u32, token)
+}
+
+fn insert(source: Source, register: impl FnOnce(u64) -> io::Result<()>) -> io::Result<u64> {
+    let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+
+    SOURCES.lock().unwrap().insert(token, source);
+
+    if let Err(e) = register(token) {
+        SOURCES.lock().unwrap().remove(&token);
+        return Err(e);
+    }
+
+    Ok(token)
+}
+
+/// Registers a non-blocking `fd` for readiness events, with no interest
+/// until `ready` asks for one. It must stay open until the registration is
+/// dropped.
+pub fn register(fd: RawFd) -> io::Result<Registration> {
+    let readiness = Arc::new(Mutex::new(Readiness::default()));
+    let token = insert(Source::Io(readiness.clone()), |token| {
+        ctl(libc::EPOLL_CTL_ADD, fd, 0, token)
+    })?;
+
+    Ok(Registration {
+        fd,
+        token,
+        readiness,
+    })
+}
+
+/// Registers `fd` for the lifetime of the process, calling `callback` on the
+/// reactor thread when it becomes readable.
+pub fn register_callback(fd: RawFd, callback: fn()) -> io::Result<()> {
+    insert(Source::Callback(callback), |token| add(fd, token)).map(drop)
+}
 
-        while let Some((when, waker)) = TIMER.pop() {
-            assert!(timers.insert(when, waker).is_none(), "duplicate timer");
+impl Registration {
+    pub fn poll_ready(&self, cx: &mut Context, interest: Interest) -> Poll<io::Result<u64>> {
+        let mut readiness = self.readiness.lock().unwrap();
+
+        let ready = match interest {
+            Interest::Readable => readiness.readable,
+            Interest::Writable => readiness.writable,
+        };
+
+        if ready {
+            return Poll::Ready(Ok(readiness.tick));
+        }
+
+        let waker = Some(cx.waker().clone());
+        match interest {
+            Interest::Readable => readiness.read_waker = waker,
+            Interest::Writable => readiness.write_waker = waker,
         }
 
-        let pending = timers.split_off(&(now + Duration::from_nanos(1)));
-        let ready = std::mem::replace(&mut timers, pending);
+        // modifying the registration reports the descriptor again if it's
+        // already ready, so nothing is missed before the interest is added
+        if readiness.events & interest.events() == 0 {
+            let events = readiness.events | interest.events();
+
+            if let Err(e) = ctl(libc::EPOLL_CTL_MOD, self.fd, events, self.token) {
+                return Poll::Ready(Err(e));
+            }
+
+            readiness.events = events;
+        }
+
+        Poll::Pending
+    }
+
+    /// Resolves once the descriptor is ready for `interest`, to the tick to
+    /// hand back to `clear`.
+    pub async fn ready(&self, interest: Interest) -> io::Result<u64> {
+        std::future::poll_fn(|cx| self.poll_ready(cx, interest)).await
+    }
+
+    /// Marks the descriptor as no longer ready for `interest`, unless an
+    /// event arrived since `tick`.
+    pub fn clear(&self, interest: Interest, tick: u64) {
+        let mut readiness = self.readiness.lock().unwrap();
 
-        for (_, waker) in ready {
-            waker.wake();
+        if readiness.tick == tick {
+            match interest {
+                Interest::Readable => readiness.readable = false,
+                Interest::Writable => readiness.writable = false,
+            }
         }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let null = std::ptr::null_mut();
+        unsafe { libc::epoll_ctl(EPOLL.as_raw_fd(), libc::EPOLL_CTL_DEL, self.fd, null) };
+
+        SOURCES.lock().unwrap().remove(&self.token);
+    }
+}
+
+/// Interrupts the reactor's wait so it picks up new timers.
+pub fn wake() {
+    let one = 1u64;
+    unsafe { libc::write(WAKE.as_raw_fd(), &one as *const u64 as _, 8) };
+}
+
+fn dispatch(token: u64, flags: u32) {
+    let Some(source) = SOURCES.lock().unwrap().get(&token).cloned() else {
+        return;
+    };
+
+    let readable = (libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLHUP | libc::EPOLLERR) as u32;
+    let writable = (libc::EPOLLOUT | libc::EPOLLHUP | libc::EPOLLERR) as u32;
 
-        let dur = timers
-            .keys()
-            .next()
-            .map(|&when| when.saturating_duration_since(now));
+    match source {
+        Source::Callback(callback) => callback(),
 
-        if let Some(dur) = dur {
-            parker.park_timeout(dur);
-        } else {
-            parker.park();
+        Source::Io(readiness) => {
+            let mut readiness = readiness.lock().unwrap();
+            readiness.tick += 1;
+
+            if flags & readable != 0 {
+                readiness.readable = true;
+
+                if let Some(waker) = readiness.read_waker.take() {
+                    waker.wake();
+                }
+            }
+
+            if flags & writable != 0 {
+                readiness.writable = true;
+
+                if let Some(waker) = readiness.write_waker.take() {
+                    waker.wake();
+                }
+            }
         }
     }
 }
 
-pub fn sleep(dur: Duration, waker: Waker) {
-    TIMER.push((Instant::now() + dur, waker));
+pub fn reactor() {
+    let epoll = EPOLL.as_raw_fd();
+    let wake = WAKE.as_raw_fd();
+
+    let mut timers = Wheel::default();
+    LazyLock::force(&START);
+    let mut events = vec![libc::epoll_event { events: 0, u64: 0 }; 256];
+
+    loop {
+        #[cfg(feature = "io-uring")]
+        super::uring::flush();
+
+        while let Some(timer) = TIMER.pop() {
+            match timer {
+                Timer::Add(id, when, waker) => {
+                    // rounded up, so timers never fire early
+                    let when = when
+                        .saturating_duration_since(*START)
+                        .as_nanos()
+                        .div_ceil(1_000_000)
+                        .min(u64::MAX as u128);
+                    timers.insert(id, when as u64, waker);
+                }
+
+                Timer::Cancel(id) => timers.cancel(id),
+            }
+        }
+
+        let now = START.elapsed().as_millis() as u64;
+        timers.advance(now);
+
+        let timeout = timers
+            .next_expiration()
+            .map(|when| when.saturating_sub(now).min(i32::MAX as u64) as i32)
+            .unwrap_or(-1);
+
+        let n = unsafe { libc::epoll_wait(epoll, events.as_mut_ptr(), events.len() as _, timeout) };
 
-    if let Some(unparker) = UNPARKER.get() {
-        unparker.unpark();
+        for event in &events[..n.max(0) as usize] {
+            let (flags, token) = (event.events, event.u64);
+
+            if token == WAKE_TOKEN {
+                let mut count = 0u64;
+                unsafe { libc::read(wake, &mut count as *mut u64 as _, 8) };
+            } else {
+                dispatch(token, flags);
+            }
+        }
     }
 }
+
+/// Wakes `waker` once `when` has passed, returning an id to cancel it with.
+pub fn add_timer(when: Instant, waker: Waker) -> u64 {
+    let id = NEXT_TIMER.fetch_add(1, Ordering::Relaxed);
+
+    TIMER.push(Timer::Add(id, when, waker));
+    wake();
+
+    id
+}
+
+pub fn cancel_timer(id: u64) {
+    TIMER.push(Timer::Cancel(id));
+}
@@ -1,10 +1,16 @@
-use std::{task::Poll, time::Duration};
+use std::{
+    task::Poll,
+    time::{Duration, Instant},
+};
 
 use super::reactor;
 
 struct SleepFuture {
-    started: bool,
-    duration: Duration,
+    /// `None` for a deadline too far off to represent, which never comes.
+    deadline: Option<Instant>,
+
+    /// The reactor timer, once registered, and the waker it will wake.
+    timer: Option<(u64, std::task::Waker)>,
 }
 
 impl Future for SleepFuture {
@@ -14,23 +20,111 @@ impl Future for SleepFuture {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Self::Output> {
-        if !self.started {
-            let waker = cx.waker().clone();
-            let duration = self.duration;
+        let Some(deadline) = self.deadline else {
+            return Poll::Pending;
+        };
+
+        if Instant::now() >= deadline {
+            if let Some((id, _)) = self.timer.take() {
+                reactor::cancel_timer(id);
+            }
+
+            return Poll::Ready(());
+        }
 
-            reactor::sleep(duration, waker);
-            self.started = true;
+        match &self.timer {
+            Some((_, waker)) if waker.will_wake(cx.waker()) => {}
 
-            Poll::Pending
-        } else {
-            Poll::Ready(())
+            _ => {
+                if let Some((id, _)) = self.timer.take() {
+                    reactor::cancel_timer(id);
+                }
+
+                let waker = cx.waker().clone();
+                let id = reactor::add_timer(deadline, waker.clone());
+                self.timer = Some((id, waker));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for SleepFuture {
+    fn drop(&mut self) {
+        if let Some((id, _)) = self.timer.take() {
+            reactor::cancel_timer(id);
         }
     }
 }
 
 pub fn sleep(duration: Duration) -> impl Future<Output = ()> {
     SleepFuture {
-        started: false,
-        duration,
+        deadline: Instant::now().checked_add(duration),
+        timer: None,
+    }
+}
+
+pub fn sleep_until(deadline: Instant) -> impl Future<Output = ()> {
+    SleepFuture {
+        deadline: Some(deadline),
+        timer: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::{
+            Arc, Once,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::{Context, Wake, Waker},
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Count(AtomicUsize);
+
+    impl Wake for Count {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Polls a sleep, then gives the reactor time to fire it if it would.
+    fn woken(duration: Duration) -> bool {
+        static REACTOR: Once = Once::new();
+        REACTOR.call_once(|| {
+            std::thread::spawn(reactor::reactor);
+        });
+
+        let count = Arc::new(Count::default());
+        let waker = Waker::from(count.clone());
+        let mut sleep = pin!(sleep(duration));
+
+        assert!(
+            sleep
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+        std::thread::sleep(Duration::from_millis(50));
+
+        count.0.load(Ordering::Relaxed) > 0
+    }
+
+    #[test]
+    fn short_sleep_fires() {
+        assert!(woken(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn huge_durations_never_fire() {
+        assert!(!woken(Duration::MAX));
+        assert!(!woken(Duration::from_secs_f64(1e19)));
+        assert!(!woken(Duration::from_secs(1 << 62)));
     }
 }
@@ -0,0 +1,326 @@
+//! File reads and writes through io_uring. Operations are queued from any
+//! thread and submitted by the reactor, which also reaps their completions.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    ffi::CString,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    task::{Poll, Waker},
+};
+
+use crossbeam::queue::SegQueue;
+use io_uring::{IoUring, Probe, opcode, squeue, types};
+
+use super::reactor;
+
+const ENTRIES: u32 = 256;
+const CHUNK: usize = 64 * 1024;
+
+struct Ring {
+    ring: Mutex<IoUring>,
+
+    /// Registered with the ring to signal completions, and with the reactor
+    /// to hear about them.
+    eventfd: OwnedFd,
+}
+
+/// The shared ring, or `None` if the kernel doesn't support the operations
+/// we need.
+static RING: OnceLock<Option<Ring>> = OnceLock::new();
+
+static QUEUE: SegQueue<Op> = SegQueue::new();
+
+/// Buffers have to outlive the operation even if its future is dropped, so
+/// they're owned here until the completion arrives.
+type Buffers = Box<dyn Any + Send>;
+
+type InFlight = HashMap<u64, (Buffers, Arc<Mutex<Completion>>)>;
+
+#[derive(Default)]
+struct Completion {
+    result: Option<(i32, Buffers)>,
+    waker: Option<Waker>,
+
+    /// Set once the future is dropped, after which nobody claims the result.
+    abandoned: bool,
+
+    /// Frees what a successful result owns if it's never claimed, like the
+    /// fd from an `OpenAt`.
+    release: Option<fn(i32)>,
+}
+
+impl Completion {
+    fn new(release: Option<fn(i32)>) -> Self {
+        Self {
+            release,
+            ..Default::default()
+        }
+    }
+
+    fn release(&mut self, result: i32) {
+        if let Some(release) = self.release.filter(|_| result >= 0) {
+            release(result);
+        }
+    }
+}
+
+/// Marks the completion abandoned when the future waiting on it is dropped,
+/// releasing a result that arrived but was never polled.
+struct Claim(Arc<Mutex<Completion>>);
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let mut completion = self.0.lock().unwrap();
+        completion.abandoned = true;
+
+        if let Some((result, _)) = completion.result.take() {
+            completion.release(result);
+        }
+    }
+}
+
+struct Op {
+    entry: squeue::Entry,
+    buffers: Buffers,
+    completion: Arc<Mutex<Completion>>,
+}
+
+thread_local! {
+    /// Operations submitted to the kernel, by user data. Only touched on the
+    /// reactor thread.
+    static IN_FLIGHT: RefCell<InFlight> = RefCell::new(HashMap::new());
+
+    /// Operations the kernel couldn't take yet, submitted before the queue
+    /// on the next turn.
+    static STALLED: RefCell<VecDeque<Op>> = const { RefCell::new(VecDeque::new()) };
+
+    static NEXT_ID: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+fn ring() -> Option<&'static Ring> {
+    RING.get_or_init(|| {
+        let ring = IoUring::new(ENTRIES).ok()?;
+
+        let mut probe = Probe::new();
+        ring.submitter().register_probe(&mut probe).ok()?;
+
+        let codes = [
+            opcode::OpenAt::CODE,
+            opcode::Read::CODE,
+            opcode::Write::CODE,
+        ];
+        if !codes.iter().all(|&code| probe.is_supported(code)) {
+            return None;
+        }
+
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return None;
+        }
+
+        let eventfd = unsafe { OwnedFd::from_raw_fd(fd) };
+        ring.submitter()
+            .register_eventfd(eventfd.as_raw_fd())
+            .ok()?;
+        reactor::register_callback(eventfd.as_raw_fd(), complete).ok()?;
+
+        Some(Ring {
+            ring: Mutex::new(ring),
+            eventfd,
+        })
+    })
+    .as_ref()
+}
+
+/// Whether io_uring is usable, falling back to the thread pool if not.
+pub fn supported() -> bool {
+    ring().is_some()
+}
+
+/// Pushes queued operations into the submission queue. Called by the reactor
+/// on every turn.
+pub fn flush() {
+    let Some(Some(ring)) = RING.get() else {
+        return;
+    };
+
+    let mut stalled = STALLED.take();
+
+    if QUEUE.is_empty() && stalled.is_empty() {
+        return;
+    }
+
+    let mut ring = ring.ring.lock().unwrap();
+
+    while let Some(op) = stalled.pop_front().or_else(|| QUEUE.pop()) {
+        let id = NEXT_ID.replace(NEXT_ID.get() + 1);
+        let entry = op.entry.clone().user_data(id);
+
+        if unsafe { ring.submission().push(&entry) }.is_err() {
+            // full, hand what's there to the kernel and try once more
+            ring.submit().ok();
+
+            if unsafe { ring.submission().push(&entry) }.is_err() {
+                // the kernel is busy, leave the rest for the next turn
+                stalled.push_front(op);
+                break;
+            }
+        }
+
+        IN_FLIGHT.with_borrow_mut(|ops| ops.insert(id, (op.buffers, op.completion)));
+    }
+
+    STALLED.set(stalled);
+    ring.submit().ok();
+}
+
+/// Hands finished operations back to their futures.
+fn complete() {
+    let Some(Some(ring)) = RING.get() else {
+        return;
+    };
+
+    let mut count = 0u64;
+    unsafe { libc::read(ring.eventfd.as_raw_fd(), &mut count as *mut u64 as _, 8) };
+
+    let mut ring = ring.ring.lock().unwrap();
+
+    for cqe in ring.completion() {
+        let Some((buffers, completion)) =
+            IN_FLIGHT.with_borrow_mut(|ops| ops.remove(&cqe.user_data()))
+        else {
+            continue;
+        };
+
+        let mut completion = completion.lock().unwrap();
+
+        if completion.abandoned {
+            completion.release(cqe.result());
+            continue;
+        }
+
+        completion.result = Some((cqe.result(), buffers));
+
+        if let Some(waker) = completion.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Queues `entry` for the reactor to submit, resolving to its result and
+/// the buffers it borrowed from. `release` frees a successful result if the
+/// future is dropped before claiming it.
+async fn submit<B: Send + 'static>(
+    entry: squeue::Entry,
+    buffers: B,
+    release: Option<fn(i32)>,
+) -> (io::Result<u32>, B) {
+    let completion = Arc::new(Mutex::new(Completion::new(release)));
+
+    QUEUE.push(Op {
+        entry,
+        buffers: Box::new(buffers),
+        completion: completion.clone(),
+    });
+    reactor::wake();
+
+    let claim = Claim(completion);
+    let (result, buffers) = std::future::poll_fn(|cx| {
+        let mut completion = claim.0.lock().unwrap();
+
+        match completion.result.take() {
+            Some(result) => Poll::Ready(result),
+
+            None => {
+                completion.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    })
+    .await;
+
+    let result = match result {
+        res if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+        res => Ok(res as u32),
+    };
+
+    (result, *buffers.downcast().unwrap())
+}
+
+async fn open(path: &Path, flags: i32) -> io::Result<OwnedFd> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let entry = opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+        .flags(flags | libc::O_CLOEXEC)
+        .mode(0o666)
+        .build();
+
+    let close = |fd| unsafe {
+        libc::close(fd);
+    };
+
+    let (fd, _) = submit(entry, path, Some(close)).await;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd? as _) })
+}
+
+// the fd goes along with the buffer, so dropping the future mid-operation
+// can't close it, and let the kernel hit whatever reuses the number, before
+// the operation completes
+
+pub async fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let mut fd = open(path, libc::O_RDONLY).await?;
+
+    let mut data = Vec::<u8>::with_capacity(CHUNK);
+
+    loop {
+        if data.capacity() == data.len() {
+            data.reserve(CHUNK);
+        }
+
+        let spare = (data.capacity() - data.len()).min(u32::MAX as usize);
+        let ptr = unsafe { data.as_mut_ptr().add(data.len()) };
+        let entry = opcode::Read::new(types::Fd(fd.as_raw_fd()), ptr, spare as u32)
+            .offset(data.len() as u64)
+            .build();
+
+        let (n, buffers) = submit(entry, (data, fd), None).await;
+        (data, fd) = buffers;
+
+        match n? {
+            0 => return Ok(data),
+            n => unsafe { data.set_len(data.len() + n as usize) },
+        }
+    }
+}
+
+pub async fn write(path: &Path, contents: Vec<u8>) -> io::Result<()> {
+    let mut fd = open(path, libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC).await?;
+
+    let mut contents = contents;
+    let mut written = 0;
+
+    while written < contents.len() {
+        let len = (contents.len() - written).min(u32::MAX as usize);
+        let ptr = unsafe { contents.as_ptr().add(written) };
+        let entry = opcode::Write::new(types::Fd(fd.as_raw_fd()), ptr, len as u32)
+            .offset(written as u64)
+            .build();
+
+        let (n, buffers) = submit(entry, (contents, fd), None).await;
+        (contents, fd) = buffers;
+
+        match n? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => written += n as usize,
+        }
+    }
+
+    Ok(())
+}
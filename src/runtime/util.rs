@@ -12,21 +12,24 @@ impl<F: FnOnce() -> T + Send + 'static, T: Send + 'static> Future for UnblockFut
     type Output = T;
 
     fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
-        if self.rx.is_empty() {
+        if let Some(func) = self.func.take() {
             let tx = self.tx.clone();
-            let func = self.func.take().unwrap();
             let waker = cx.waker().clone();
             rayon::spawn(move || {
                 let result = func();
-                tx.send(result).unwrap();
+                // the future may have been dropped in the meantime
+                tx.send(result).ok();
 
                 waker.wake_by_ref();
             });
 
             Poll::Pending
         } else {
-            let result = self.rx.recv().unwrap();
-            Poll::Ready(result)
+            // polled again before the closure finished
+            match self.rx.try_recv() {
+                Ok(result) => Poll::Ready(result),
+                Err(_) => Poll::Pending,
+            }
         }
     }
 }
@@ -0,0 +1,222 @@
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Poll, Waker},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Symlinks {
+    /// Yield links without descending into them.
+    #[default]
+    Include,
+    /// Descend into linked directories, yielding what the links point at.
+    Follow,
+    /// Leave links out entirely.
+    Skip,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub max_depth: Option<usize>,
+    pub symlinks: Symlinks,
+
+    /// Honor `.gitignore`, `.ignore` and git's exclude files.
+    pub ignore: bool,
+
+    /// Include entries whose name starts with a dot.
+    pub hidden: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            symlinks: Symlinks::Include,
+            ignore: true,
+            hidden: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub kind: &'static str,
+}
+
+#[derive(Default)]
+struct State {
+    entries: VecDeque<io::Result<Entry>>,
+    wakers: Vec<Waker>,
+    done: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    cancelled: AtomicBool,
+}
+
+impl Shared {
+    fn push(&self, entry: io::Result<Entry>) {
+        let mut state = self.state.lock().unwrap();
+
+        state.entries.push_back(entry);
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+
+    fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        state.done = true;
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// A directory traversal running on the rayon pool. Entries are buffered
+/// until read, and dropping the walk stops the traversal.
+pub struct Walk {
+    shared: Arc<Shared>,
+}
+
+impl Walk {
+    /// Resolves to the next entry, or `None` once the traversal is over.
+    pub fn next(&self) -> impl Future<Output = Option<io::Result<Entry>>> + 'static {
+        let shared = self.shared.clone();
+
+        std::future::poll_fn(move |cx| {
+            let mut state = shared.state.lock().unwrap();
+
+            if let Some(entry) = state.entries.pop_front() {
+                Poll::Ready(Some(entry))
+            } else if state.done {
+                Poll::Ready(None)
+            } else {
+                state.wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl Drop for Walk {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Walks everything below `root`, in a stable order.
+pub fn walk<P: AsRef<Path>>(root: P, options: WalkOptions) -> Walk {
+    start(root.as_ref().to_owned(), None, options)
+}
+
+/// Walks the paths matching `pattern`, where `*` and `?` stay within one
+/// component and `**` matches any number of them.
+pub fn glob(pattern: &str, mut options: WalkOptions) -> io::Result<Walk> {
+    let path = Path::new(pattern);
+
+    // the literal components before the first wildcard are where the walk starts
+    let literal = path
+        .components()
+        .take_while(|c| {
+            !c.as_os_str()
+                .as_encoded_bytes()
+                .iter()
+                .any(|b| b"*?[{".contains(b))
+        })
+        .count();
+    let literal = literal.min(path.components().count().saturating_sub(1));
+
+    let base: PathBuf = path.components().take(literal).collect();
+    let rest: PathBuf = path.components().skip(literal).collect();
+
+    if !rest
+        .as_os_str()
+        .as_encoded_bytes()
+        .windows(2)
+        .any(|w| w == b"**")
+    {
+        let depth = rest.components().count();
+        options.max_depth = Some(options.max_depth.map_or(depth, |max| max.min(depth)));
+    }
+
+    let matcher = GlobBuilder::new(&rest.to_string_lossy())
+        .literal_separator(true)
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?
+        .compile_matcher();
+
+    let root = match base.as_os_str().is_empty() {
+        true => PathBuf::from(Component::CurDir.as_os_str()),
+        false => base.clone(),
+    };
+
+    Ok(start(root, Some((base, matcher)), options))
+}
+
+fn start(root: PathBuf, filter: Option<(PathBuf, GlobMatcher)>, options: WalkOptions) -> Walk {
+    let shared = Arc::new(Shared::default());
+
+    let producer = shared.clone();
+    rayon::spawn(move || {
+        let walker = ignore::WalkBuilder::new(&root)
+            .max_depth(options.max_depth)
+            .follow_links(options.symlinks == Symlinks::Follow)
+            .standard_filters(options.ignore)
+            .hidden(!options.hidden)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+
+        for entry in walker {
+            if producer.cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let entry = match entry {
+                Ok(entry) if entry.depth() == 0 => continue,
+                Ok(entry) => entry,
+
+                Err(e) => {
+                    let kind = e.io_error().map_or(io::ErrorKind::Other, io::Error::kind);
+                    producer.push(Err(io::Error::new(kind, e.to_string())));
+                    continue;
+                }
+            };
+
+            let kind = match entry.file_type() {
+                _ if entry.path_is_symlink() && options.symlinks == Symlinks::Skip => continue,
+                _ if entry.path_is_symlink() && options.symlinks == Symlinks::Include => "symlink",
+                Some(t) if t.is_dir() => "dir",
+                Some(t) if t.is_file() => "file",
+                _ => "other",
+            };
+
+            let path = match &filter {
+                None => entry.into_path(),
+
+                Some((base, matcher)) => {
+                    let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                    if !matcher.is_match(relative) {
+                        continue;
+                    }
+
+                    base.join(relative)
+                }
+            };
+
+            producer.push(Ok(Entry { path, kind }));
+        }
+
+        producer.finish();
+    });
+
+    Walk { shared }
+}
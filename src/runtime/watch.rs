@@ -0,0 +1,401 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::{CString, OsStr, c_int},
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex, OnceLock, Weak},
+    task::{Poll, Waker},
+};
+
+use super::{reactor, util::unblock};
+
+const MASK: u32 = libc::IN_CREATE
+    | libc::IN_MODIFY
+    | libc::IN_DELETE
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO;
+
+/// The inotify instance shared by every watcher, or the errno creating it
+/// failed with.
+static INOTIFY: OnceLock<Result<OwnedFd, i32>> = OnceLock::new();
+
+/// Watch descriptors and the watchers interested in them. The kernel hands
+/// out one descriptor per inode, so watchers of the same directory share it.
+static WATCHES: LazyLock<Mutex<HashMap<c_int, Vec<Entry>>>> = LazyLock::new(Default::default);
+
+struct Entry {
+    watcher: Weak<Shared>,
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Create,
+    Modify,
+    Remove,
+    Rename,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Modify => "modify",
+            Self::Remove => "remove",
+            Self::Rename => "rename",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Event {
+    pub kind: EventKind,
+    pub path: PathBuf,
+
+    /// The old path of a renamed entry.
+    pub from: Option<PathBuf>,
+}
+
+struct Shared {
+    root: PathBuf,
+    recursive: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    events: VecDeque<Event>,
+    wakers: Vec<Waker>,
+    wds: Vec<c_int>,
+    closed: bool,
+}
+
+impl Shared {
+    fn push(&self, kind: EventKind, path: PathBuf, from: Option<PathBuf>) {
+        let mut state = self.state.lock().unwrap();
+
+        if !state.closed {
+            state.events.push_back(Event { kind, path, from });
+            state.wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+/// Watches a file or directory for changes. Dropping the watcher removes
+/// its inotify watches.
+pub struct Watcher {
+    shared: Arc<Shared>,
+}
+
+/// Starts watching `path`, and every directory below it if `recursive` is
+/// set.
+pub async fn watch<P: AsRef<Path>>(path: P, recursive: bool) -> io::Result<Watcher> {
+    let path = path.as_ref().to_owned();
+    unblock(move || {
+        let shared = Arc::new(Shared {
+            root: path.clone(),
+            recursive,
+            state: Mutex::default(),
+        });
+
+        let watcher = Watcher { shared };
+        add(&watcher.shared, &path, &mut Vec::new())?;
+
+        Ok(watcher)
+    })
+    .await
+}
+
+impl Watcher {
+    /// Resolves to the next event, or `None` once the watcher is closed.
+    pub fn next(&self) -> impl Future<Output = Option<Event>> + 'static {
+        let shared = self.shared.clone();
+
+        std::future::poll_fn(move |cx| {
+            let mut state = shared.state.lock().unwrap();
+
+            if let Some(event) = state.events.pop_front() {
+                Poll::Ready(Some(event))
+            } else if state.closed {
+                Poll::Ready(None)
+            } else {
+                state.wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Removes the watches and resolves every pending `next` with `None`.
+    pub fn close(&self) {
+        let wds = {
+            let mut state = self.shared.state.lock().unwrap();
+
+            state.closed = true;
+            state.events.clear();
+            state.wakers.drain(..).for_each(Waker::wake);
+
+            std::mem::take(&mut state.wds)
+        };
+
+        let mut watches = WATCHES.lock().unwrap();
+        for wd in wds {
+            forget(&mut watches, wd, |entry| {
+                std::ptr::eq(entry.watcher.as_ptr(), Arc::as_ptr(&self.shared))
+            });
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// The inotify descriptor, once one exists.
+fn inotify_fd() -> Option<RawFd> {
+    INOTIFY.get()?.as_ref().ok().map(AsRawFd::as_raw_fd)
+}
+
+fn inotify() -> io::Result<RawFd> {
+    let fd = INOTIFY.get_or_init(|| {
+        let fd = match unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) } {
+            -1 => return Err(io::Error::last_os_error().raw_os_error().unwrap_or(0)),
+            fd => unsafe { OwnedFd::from_raw_fd(fd) },
+        };
+
+        match reactor::register_callback(fd.as_raw_fd(), dispatch) {
+            Ok(()) => Ok(fd),
+            Err(e) => Err(e.raw_os_error().unwrap_or(0)),
+        }
+    });
+
+    match fd {
+        Ok(fd) => Ok(fd.as_raw_fd()),
+        Err(errno) => Err(io::Error::from_raw_os_error(*errno)),
+    }
+}
+
+/// Adds a watch on `path` for `shared`, and on its subdirectories if the
+/// watcher is recursive. Entries found while descending are appended to
+/// `found`.
+fn add(shared: &Arc<Shared>, path: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    let fd = inotify()?;
+    let cpath = CString::new(path.as_os_str().as_bytes())?;
+
+    let wd = unsafe { libc::inotify_add_watch(fd, cpath.as_ptr(), MASK) };
+    if wd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    {
+        let mut watches = WATCHES.lock().unwrap();
+        let entries = watches.entry(wd).or_default();
+
+        let watcher = Arc::downgrade(shared);
+        if entries.iter().any(|e| e.watcher.ptr_eq(&watcher)) {
+            return Ok(());
+        }
+
+        let mut state = shared.state.lock().unwrap();
+        if state.closed {
+            drop(state);
+            forget(&mut watches, wd, |_| false);
+            return Ok(());
+        }
+
+        entries.push(Entry {
+            watcher,
+            path: path.to_owned(),
+        });
+        state.wds.push(wd);
+    }
+
+    if shared.recursive && std::fs::symlink_metadata(path)?.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let child = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                match add(shared, &child, found) {
+                    // removed while we were walking
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    result => result?,
+                }
+            }
+
+            found.push(child);
+        }
+    }
+
+    Ok(())
+}
+
+/// Drops the entries of `wd` matching `pred`, removing the watch from the
+/// kernel once nobody is left interested in it.
+fn forget(watches: &mut HashMap<c_int, Vec<Entry>>, wd: c_int, pred: impl Fn(&Entry) -> bool) {
+    let Some(entries) = watches.get_mut(&wd) else {
+        return;
+    };
+
+    entries.retain(|entry| !pred(entry));
+
+    if entries.is_empty() {
+        watches.remove(&wd);
+
+        if let Some(fd) = inotify_fd() {
+            unsafe { libc::inotify_rm_watch(fd, wd) };
+        }
+    }
+}
+
+/// Rewrites the watched paths of `shared` below `from` to sit below `to`
+/// after a rename, or drops them if the directory left the watched tree.
+fn rebase(shared: &Arc<Shared>, from: &Path, to: Option<&Path>) {
+    let watcher = Arc::downgrade(shared);
+    let mut watches = WATCHES.lock().unwrap();
+    let mut gone = Vec::new();
+
+    for (&wd, entries) in watches.iter_mut() {
+        for entry in entries.iter_mut().filter(|e| e.watcher.ptr_eq(&watcher)) {
+            if let Ok(rest) = entry.path.strip_prefix(from) {
+                match to {
+                    Some(to) if rest.as_os_str().is_empty() => entry.path = to.to_owned(),
+                    Some(to) => entry.path = to.join(rest),
+                    None => gone.push(wd),
+                }
+            }
+        }
+    }
+
+    for wd in gone {
+        forget(&mut watches, wd, |e| e.watcher.ptr_eq(&watcher));
+        shared.state.lock().unwrap().wds.retain(|&w| w != wd);
+    }
+}
+
+/// Reads every pending inotify event and hands them to their watchers.
+/// Called by the reactor when the inotify descriptor is readable.
+fn dispatch() {
+    let Some(fd) = inotify_fd() else {
+        return;
+    };
+
+    let mut data = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+
+    loop {
+        match unsafe { libc::read(fd, chunk.as_mut_ptr() as _, chunk.len()) } {
+            n if n > 0 => data.extend_from_slice(&chunk[..n as usize]),
+            _ => break,
+        }
+    }
+
+    // MOVED_FROM halves waiting for the MOVED_TO with the same cookie
+    let mut moves: Vec<(u32, Arc<Shared>, PathBuf, bool)> = Vec::new();
+    let mut offset = 0;
+
+    while offset + size_of::<libc::inotify_event>() <= data.len() {
+        let event: libc::inotify_event =
+            unsafe { std::ptr::read_unaligned(data[offset..].as_ptr() as _) };
+        let start = offset + size_of::<libc::inotify_event>();
+        offset = start + event.len as usize;
+
+        let name = &data[start..offset.min(data.len())];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+
+        if event.mask & libc::IN_IGNORED != 0 {
+            if let Some(entries) = WATCHES.lock().unwrap().remove(&event.wd) {
+                for shared in entries.iter().filter_map(|e| e.watcher.upgrade()) {
+                    shared.state.lock().unwrap().wds.retain(|&w| w != event.wd);
+                }
+            }
+
+            continue;
+        }
+
+        let targets: Vec<(Arc<Shared>, PathBuf)> = match WATCHES.lock().unwrap().get(&event.wd) {
+            Some(entries) => entries
+                .iter()
+                .filter_map(|e| Some((e.watcher.upgrade()?, e.path.clone())))
+                .collect(),
+            None => continue,
+        };
+
+        let is_dir = event.mask & libc::IN_ISDIR != 0;
+
+        for (shared, dir) in targets {
+            let path = match name.is_empty() {
+                true => dir.clone(),
+                false => dir.join(OsStr::from_bytes(name)),
+            };
+
+            if event.mask & libc::IN_CREATE != 0 {
+                shared.push(EventKind::Create, path.clone(), None);
+
+                if is_dir && shared.recursive {
+                    created(&shared, &path);
+                }
+            } else if event.mask & libc::IN_MODIFY != 0 {
+                shared.push(EventKind::Modify, path, None);
+            } else if event.mask & libc::IN_DELETE != 0 {
+                shared.push(EventKind::Remove, path, None);
+            } else if event.mask & libc::IN_DELETE_SELF != 0 {
+                // deleted subdirectories were already reported by their parent
+                if dir == shared.root {
+                    shared.push(EventKind::Remove, path, None);
+                }
+            } else if event.mask & libc::IN_MOVED_FROM != 0 {
+                moves.push((event.cookie, shared, path, is_dir));
+            } else if event.mask & libc::IN_MOVED_TO != 0 {
+                let pair = moves
+                    .iter()
+                    .position(|(cookie, s, ..)| *cookie == event.cookie && Arc::ptr_eq(s, &shared));
+
+                match pair.map(|i| moves.remove(i)) {
+                    Some((_, _, from, _)) => {
+                        if is_dir && shared.recursive {
+                            rebase(&shared, &from, Some(&path));
+                        }
+
+                        shared.push(EventKind::Rename, path, Some(from));
+                    }
+
+                    None => {
+                        shared.push(EventKind::Create, path.clone(), None);
+
+                        if is_dir && shared.recursive {
+                            created(&shared, &path);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // moved out of the watched tree
+    for (_, shared, path, is_dir) in moves {
+        if is_dir && shared.recursive {
+            rebase(&shared, &path, None);
+        }
+
+        shared.push(EventKind::Remove, path, None);
+    }
+}
+
+/// Starts watching a directory that appeared inside a recursive watch, and
+/// reports whatever was created in it before the watch was in place.
+fn created(shared: &Arc<Shared>, path: &Path) {
+    let mut found = Vec::new();
+
+    if add(shared, path, &mut found).is_ok() {
+        for path in found {
+            shared.push(EventKind::Create, path, None);
+        }
+    }
+}
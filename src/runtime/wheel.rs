@@ -0,0 +1,356 @@
+//! A hierarchical timer wheel. Each level has 64 slots, and a slot on level
+//! `n` spans 64^n milliseconds, so six levels cover a little over two years.
+//! Timers start on the level matching how far away they are and cascade
+//! down as their deadline approaches, which keeps insertion, cancellation
+//! and expiry constant time regardless of how many timers are pending.
+
+use std::{collections::HashMap, task::Waker};
+
+const LEVELS: usize = 6;
+const SLOTS: usize = 64;
+const SLOT_BITS: u32 = SLOTS.trailing_zeros();
+
+/// The furthest a timer can be placed from the current time. Later deadlines
+/// are parked there and re-inserted when they come up.
+const MAX_SPAN: u64 = (1 << (SLOT_BITS * LEVELS as u32)) - 1;
+
+struct Entry {
+    id: u64,
+    when: u64,
+    waker: Waker,
+
+    level: usize,
+    slot: usize,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct Level {
+    /// Bit `n` is set when slot `n` has entries.
+    occupied: u64,
+    heads: [Option<usize>; SLOTS],
+}
+
+pub struct Wheel {
+    /// Milliseconds processed so far.
+    elapsed: u64,
+    levels: [Level; LEVELS],
+
+    entries: Vec<Option<Entry>>,
+    free: Vec<usize>,
+    ids: HashMap<u64, usize>,
+}
+
+fn slot_range(level: usize) -> u64 {
+    1 << (SLOT_BITS * level as u32)
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Self {
+            elapsed: 0,
+            levels: [Level {
+                occupied: 0,
+                heads: [None; SLOTS],
+            }; LEVELS],
+            entries: Vec::new(),
+            free: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+}
+
+impl Wheel {
+    /// Adds a timer waking `waker` once `when` milliseconds have elapsed.
+    /// Timers already due are woken on the next `advance`.
+    pub fn insert(&mut self, id: u64, when: u64, waker: Waker) {
+        let entry = Entry {
+            id,
+            when,
+            waker,
+            level: 0,
+            slot: 0,
+            prev: None,
+            next: None,
+        };
+
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.entries[idx] = Some(entry);
+                idx
+            }
+
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+
+        self.ids.insert(id, idx);
+        self.link(idx);
+    }
+
+    /// Removes a pending timer without waking it.
+    pub fn cancel(&mut self, id: u64) {
+        if let Some(idx) = self.ids.remove(&id) {
+            self.unlink(idx);
+            self.entries[idx] = None;
+            self.free.push(idx);
+        }
+    }
+
+    /// The position of a deadline: the level is picked by the highest bit
+    /// it differs from the current time in. Clamped deadlines can still carry
+    /// into the bit above the top level, so the level is capped too.
+    fn position(&self, when: u64) -> (usize, usize) {
+        let when = when.clamp(self.elapsed, self.elapsed + MAX_SPAN);
+
+        let masked = (self.elapsed ^ when) | (SLOTS as u64 - 1);
+        let level = ((63 - masked.leading_zeros()) / SLOT_BITS) as usize;
+        let level = level.min(LEVELS - 1);
+        let slot = (when >> (SLOT_BITS * level as u32)) as usize % SLOTS;
+
+        (level, slot)
+    }
+
+    fn link(&mut self, idx: usize) {
+        let (level, slot) = self.position(self.entries[idx].as_ref().unwrap().when);
+        let head = self.levels[level].heads[slot];
+
+        if let Some(head) = head {
+            self.entries[head].as_mut().unwrap().prev = Some(idx);
+        }
+
+        let entry = self.entries[idx].as_mut().unwrap();
+        (entry.level, entry.slot) = (level, slot);
+        (entry.prev, entry.next) = (None, head);
+
+        self.levels[level].heads[slot] = Some(idx);
+        self.levels[level].occupied |= 1 << slot;
+    }
+
+    fn unlink(&mut self, idx: usize) {
+        let entry = self.entries[idx].as_ref().unwrap();
+        let (level, slot, prev, next) = (entry.level, entry.slot, entry.prev, entry.next);
+
+        if let Some(next) = next {
+            self.entries[next].as_mut().unwrap().prev = prev;
+        }
+
+        match prev {
+            Some(prev) => self.entries[prev].as_mut().unwrap().next = next,
+
+            None => {
+                self.levels[level].heads[slot] = next;
+
+                if next.is_none() {
+                    self.levels[level].occupied &= !(1 << slot);
+                }
+            }
+        }
+    }
+
+    /// The time the next slot needs processing, if any timers are pending.
+    pub fn next_expiration(&self) -> Option<u64> {
+        self.next_slot().map(|(_, _, when)| when)
+    }
+
+    fn next_slot(&self) -> Option<(usize, usize, u64)> {
+        (0..LEVELS).find_map(|level| {
+            self.level_expiration(level)
+                .map(|(slot, when)| (level, slot, when))
+        })
+    }
+
+    fn level_expiration(&self, level: usize) -> Option<(usize, u64)> {
+        let occupied = self.levels[level].occupied;
+        if occupied == 0 {
+            return None;
+        }
+
+        let range = slot_range(level);
+        let now_slot = self.elapsed / range;
+
+        let zeros = occupied
+            .rotate_right((now_slot % SLOTS as u64) as u32)
+            .trailing_zeros();
+        let slot = (now_slot as usize + zeros as usize) % SLOTS;
+
+        let level_range = range * SLOTS as u64;
+        let level_start = self.elapsed & !(level_range - 1);
+
+        let mut when = level_start + slot as u64 * range;
+        if when < self.elapsed {
+            when += level_range;
+        }
+
+        Some((slot, when))
+    }
+
+    /// Processes everything due at or before `now`, waking expired timers
+    /// and moving the rest down a level.
+    pub fn advance(&mut self, now: u64) {
+        while let Some((level, slot, when)) = self.next_slot() {
+            if when > now {
+                break;
+            }
+
+            self.elapsed = when;
+
+            let mut next = self.levels[level].heads[slot].take();
+            self.levels[level].occupied &= !(1 << slot);
+
+            while let Some(idx) = next {
+                let entry = self.entries[idx].as_ref().unwrap();
+                next = entry.next;
+
+                if entry.when <= self.elapsed {
+                    let entry = self.entries[idx].take().unwrap();
+
+                    self.ids.remove(&entry.id);
+                    self.free.push(idx);
+                    entry.waker.wake();
+                } else {
+                    self.link(idx);
+                }
+            }
+        }
+
+        self.elapsed = self.elapsed.max(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::Wake,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Count(AtomicUsize);
+
+    impl Wake for Count {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    impl Count {
+        fn get(&self) -> usize {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    fn timer(wheel: &mut Wheel, id: u64, when: u64) -> Arc<Count> {
+        let count = Arc::new(Count::default());
+        wheel.insert(id, when, Waker::from(count.clone()));
+        count
+    }
+
+    #[test]
+    fn fires_at_deadline() {
+        let mut wheel = Wheel::default();
+        let count = timer(&mut wheel, 0, 10);
+
+        assert_eq!(wheel.next_expiration(), Some(10));
+
+        wheel.advance(9);
+        assert_eq!(count.get(), 0);
+
+        wheel.advance(10);
+        assert_eq!(count.get(), 1);
+        assert_eq!(wheel.next_expiration(), None);
+    }
+
+    #[test]
+    fn past_deadline_fires_on_next_advance() {
+        let mut wheel = Wheel::default();
+        wheel.advance(100);
+
+        let count = timer(&mut wheel, 0, 50);
+
+        wheel.advance(100);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut wheel = Wheel::default();
+        let cancelled = timer(&mut wheel, 0, 10);
+        let kept = timer(&mut wheel, 1, 10);
+
+        wheel.cancel(0);
+        wheel.cancel(0);
+        wheel.advance(10);
+
+        assert_eq!(cancelled.get(), 0);
+        assert_eq!(kept.get(), 1);
+    }
+
+    #[test]
+    fn cascades_down_levels() {
+        let mut wheel = Wheel::default();
+        let counts =
+            [70, 5000, 300_000, 20_000_000].map(|when| (when, timer(&mut wheel, when, when)));
+
+        for (when, count) in &counts {
+            wheel.advance(when - 1);
+            assert_eq!(count.get(), 0, "{when} fired early");
+
+            wheel.advance(*when);
+            assert_eq!(count.get(), 1, "{when} didn't fire");
+        }
+    }
+
+    #[test]
+    fn far_deadline() {
+        let mut wheel = Wheel::default();
+        wheel.advance(500);
+
+        let when = 100_000_000_000;
+        let count = timer(&mut wheel, 0, when);
+
+        wheel.advance(MAX_SPAN);
+        assert_eq!(count.get(), 0);
+
+        wheel.advance(when - 1);
+        assert_eq!(count.get(), 0);
+
+        wheel.advance(when);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn duplicate_deadlines() {
+        let mut wheel = Wheel::default();
+        let counts: Vec<_> = (0..3).map(|id| timer(&mut wheel, id, 1000)).collect();
+
+        wheel.cancel(1);
+        wheel.advance(1000);
+
+        let fired: Vec<_> = counts.iter().map(|count| count.get()).collect();
+        assert_eq!(fired, [1, 0, 1]);
+    }
+
+    #[test]
+    fn reuses_freed_entries() {
+        let mut wheel = Wheel::default();
+
+        for round in 0..3 {
+            let when = (round + 1) * 100;
+            let count = timer(&mut wheel, round, when);
+
+            wheel.advance(when);
+            assert_eq!(count.get(), 1);
+        }
+
+        assert_eq!(wheel.entries.len(), 1);
+    }
+}
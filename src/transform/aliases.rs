@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use super::{
+    lexer::{Kind, Token},
+    syntax::Source,
+};
+use crate::luaurc::{self, normalize};
+
+fn string_literal(token: &Token) -> Option<(char, &str)> {
+    let quote = token.text.chars().next()?;
+
+    if token.kind != Kind::String || !matches!(quote, '"' | '\'') || token.text.contains('\\') {
+        return None;
+    }
+
+    Some((quote, &token.text[1..token.text.len() - 1]))
+}
+
+pub fn rewrite_requires(tokens: Vec<Token>, file: &Path) -> Result<Vec<Token>, String> {
+    let dir = file
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve '{}': {e}", file.display()))?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let aliases = luaurc::aliases(&dir)?;
+    let source = Source::new(tokens);
+    let mut replace = Vec::new();
+
+    for p in 0..source.len() {
+        if !source.is(p, "require") || p.checked_sub(1).is_some_and(|prev| source.is(prev, ".")) {
+            continue;
+        }
+
+        let arg = if source.is(p + 1, "(") && source.is(p + 3, ")") {
+            p + 2
+        } else {
+            p + 1
+        };
+
+        let Some((quote, path)) = source.get(arg).and_then(string_literal) else {
+            continue;
+        };
+
+        if !path.starts_with("./") && !path.starts_with("../") {
+            continue;
+        }
+
+        let resolved = normalize(&dir.join(path));
+        let Some((name, rest)) = aliases
+            .iter()
+            .filter_map(|a| Some((&a.name, &a.target, resolved.strip_prefix(&a.target).ok()?)))
+            .max_by_key(|(_, target, _)| target.components().count())
+            .map(|(name, _, rest)| (name, rest))
+        else {
+            continue;
+        };
+
+        let mut text = format!("{quote}@{name}");
+        for component in rest.components() {
+            text.push('/');
+            text.push_str(&component.as_os_str().to_string_lossy());
+        }
+        text.push(quote);
+
+        replace.push((source.index(arg), Token::new(Kind::String, text)));
+    }
+
+    let mut tokens = source.tokens;
+    for (i, token) in replace {
+        tokens[i] = token;
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    lexer::{Kind, Token, lex},
+    syntax::{Edits, Source},
+    types::{is_type_statement, type_statement_end},
+};
+
+pub fn parse_define(define: &str) -> Result<(String, Token), String> {
+    let (name, value) = define.split_once('=').unwrap_or((define, "true"));
+
+    match lex(name).as_deref() {
+        Ok([token]) if token.is_name() => {}
+        _ => return Err(format!("invalid define name '{name}'")),
+    }
+
+    let value = match lex(value).as_deref() {
+        Ok([token]) if matches!(token.kind, Kind::Number | Kind::String) => token.clone(),
+        Ok([token]) if token.is("true") || token.is("false") || token.is("nil") => token.clone(),
+        _ => Token::new(Kind::String, format!("{value:?}")),
+    };
+
+    Ok((name.to_owned(), value))
+}
+
+const COMPOUND: &[&str] = &["+=", "-=", "*=", "/=", "//=", "%=", "^=", "..="];
+
+enum Frame {
+    /// A block, with the locals declared in it so far.
+    Scope(HashSet<String>),
+    /// An `if` expression, open until its `else`.
+    ExprIf,
+    /// An open `(`, `[` or `{`, and whether it's a table constructor.
+    Bracket(bool),
+}
+
+struct Scopes {
+    frames: Vec<Frame>,
+
+    /// `for` loops whose scope is already open, waiting for their `do`.
+    loops: usize,
+}
+
+impl Scopes {
+    fn declare(&mut self, name: &str) {
+        let scope = self.frames.iter_mut().rev().find_map(|frame| match frame {
+            Frame::Scope(names) => Some(names),
+            _ => None,
+        });
+
+        if let Some(names) = scope {
+            names.insert(name.to_owned());
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.frames
+            .iter()
+            .any(|frame| matches!(frame, Frame::Scope(names) if names.contains(name)))
+    }
+
+    /// The innermost open bracket, unless a block was opened inside it.
+    fn bracket(&self) -> Option<bool> {
+        self.frames.iter().rev().find_map(|frame| match frame {
+            Frame::Scope(_) => Some(None),
+            Frame::ExprIf => None,
+            Frame::Bracket(table) => Some(Some(*table)),
+        })?
+    }
+
+    fn open(&mut self) {
+        self.frames.push(Frame::Scope(HashSet::new()));
+    }
+
+    /// Pops frames up to and including the innermost one matching `pred`.
+    fn close(&mut self, pred: impl Fn(&Frame) -> bool) {
+        if let Some(i) = self.frames.iter().rposition(pred) {
+            self.frames.truncate(i.max(1));
+        }
+    }
+
+    fn in_expr_if(&self) -> bool {
+        matches!(self.frames.last(), Some(Frame::ExprIf))
+    }
+}
+
+/// Declares the comma separated names starting at `p`, skipping their type
+/// annotations, and returns the position after them.
+fn declare_names(source: &Source, scopes: &mut Scopes, mut p: usize) -> usize {
+    while source.is_name(p) || source.is(p, "...") {
+        scopes.declare(&source.get(p).unwrap().text);
+        p += 1;
+
+        if source.is(p, ":") {
+            p = source.skip_type(p + 1);
+        }
+
+        if source.is(p, ",") {
+            p += 1;
+        } else {
+            break;
+        }
+    }
+
+    p
+}
+
+/// Skips the field accesses, indexes and calls following a prefix
+/// expression at `p`.
+fn skip_suffixes(source: &Source, mut p: usize) -> usize {
+    loop {
+        match source.get(p) {
+            Some(t) if (t.is(".") || t.is(":")) && source.is_name(p + 1) => p += 2,
+            Some(t) if t.is("(") || t.is("[") || t.is("{") => p = source.skip_balanced(p),
+            Some(t) if t.kind == Kind::String => p += 1,
+            _ => return p,
+        }
+    }
+}
+
+/// Whether the list of prefix expressions continuing at `p` is the target
+/// of an assignment.
+fn assigned(source: &Source, mut p: usize) -> bool {
+    while source.is(p, ",") {
+        p += 1;
+
+        if source.is_name(p) {
+            p = skip_suffixes(source, p + 1);
+        } else if source.is(p, "(") {
+            p = skip_suffixes(source, source.skip_balanced(p));
+        } else {
+            return false;
+        }
+    }
+
+    source.is(p, "=") || COMPOUND.iter().any(|op| source.is(p, op))
+}
+
+/// Replaces reads of the globals in `defines` with their values. Locals,
+/// parameters, fields, assignment targets and types sharing a define's name
+/// are left alone.
+pub fn inline_defines(tokens: Vec<Token>, defines: &HashMap<String, Token>) -> Vec<Token> {
+    let source = Source::new(tokens);
+    let mut edits = Edits::new();
+
+    let mut scopes = Scopes {
+        frames: vec![Frame::Scope(HashSet::new())],
+        loops: 0,
+    };
+
+    let mut p = 0;
+
+    while let Some(token) = source.get(p) {
+        if token.kind == Kind::String || token.kind == Kind::Number {
+            p += 1;
+            continue;
+        }
+
+        match token.text.as_str() {
+            "(" | "[" | "{" => {
+                scopes.frames.push(Frame::Bracket(token.text == "{"));
+                p += 1;
+            }
+
+            ")" | "]" | "}" => {
+                scopes.close(|frame| matches!(frame, Frame::Bracket(_)));
+                p += 1;
+            }
+
+            "local" if source.is(p + 1, "function") => {
+                if let Some(name) = source.get(p + 2).filter(|t| t.is_name()) {
+                    scopes.declare(&name.text);
+                }
+
+                p += 1;
+            }
+
+            "local" => p = declare_names(&source, &mut scopes, p + 1),
+
+            "for" => {
+                scopes.open();
+                scopes.loops += 1;
+                p = declare_names(&source, &mut scopes, p + 1);
+            }
+
+            "function" => {
+                let mut q = p + 1;
+                let mut method = false;
+
+                if source.is_name(q) {
+                    q += 1;
+
+                    while (source.is(q, ".") || source.is(q, ":")) && source.is_name(q + 1) {
+                        method = source.is(q, ":");
+                        q += 2;
+                    }
+                }
+
+                if source.is(q, "<") {
+                    q = source.skip_balanced(q);
+                }
+
+                scopes.open();
+
+                if method {
+                    scopes.declare("self");
+                }
+
+                if source.is(q, "(") {
+                    q = declare_names(&source, &mut scopes, q + 1);
+
+                    if source.is(q, ")") {
+                        q += 1;
+                    }
+
+                    if source.is(q, ":") {
+                        q = source.skip_type(q + 1);
+                    }
+                }
+
+                p = q;
+            }
+
+            // types never hold values
+            "::" => p = source.skip_type(p + 1),
+            "export" if is_type_statement(&source, p + 1) => p = type_statement_end(&source, p + 1),
+
+            "type" if is_type_statement(&source, p) => p = type_statement_end(&source, p),
+
+            "do" if scopes.loops > 0 => {
+                scopes.loops -= 1;
+                p += 1;
+            }
+
+            "do" | "repeat" => {
+                scopes.open();
+                p += 1;
+            }
+
+            "if" => {
+                if !source.is_statement_if(p) {
+                    scopes.frames.push(Frame::ExprIf);
+                }
+
+                p += 1;
+            }
+
+            "then" => {
+                if !scopes.in_expr_if() {
+                    scopes.open();
+                }
+
+                p += 1;
+            }
+
+            "elseif" => {
+                if !scopes.in_expr_if() {
+                    scopes.close(|frame| matches!(frame, Frame::Scope(_)));
+                }
+
+                p += 1;
+            }
+
+            "else" if scopes.in_expr_if() => {
+                scopes.frames.pop();
+                p += 1;
+            }
+
+            "else" => {
+                scopes.close(|frame| matches!(frame, Frame::Scope(_)));
+                scopes.open();
+                p += 1;
+            }
+
+            "end" | "until" => {
+                scopes.close(|frame| matches!(frame, Frame::Scope(_)));
+                p += 1;
+            }
+
+            _ => {
+                if let Some(value) = defines.get(&token.text).filter(|_| token.is_name()) {
+                    inline(&source, &scopes, &mut edits, p, value);
+                }
+
+                p += 1;
+            }
+        }
+    }
+
+    edits.apply(source.tokens)
+}
+
+fn inline(source: &Source, scopes: &Scopes, edits: &mut Edits, p: usize, value: &Token) {
+    let name = &source.get(p).unwrap().text;
+
+    // fields and methods
+    let member = p
+        .checked_sub(1)
+        .is_some_and(|prev| source.is(prev, ".") || source.is(prev, ":"));
+
+    if member || scopes.is_local(name) {
+        return;
+    }
+
+    let end = skip_suffixes(source, p + 1);
+
+    let target = match scopes.bracket() {
+        // table keys
+        Some(true) => source.is(p + 1, "="),
+        Some(false) => false,
+        None => end == p + 1 && assigned(source, end),
+    };
+
+    if target {
+        return;
+    }
+
+    // `true.x` and `"a":upper()` don't parse
+    if end > p + 1 {
+        let wrapped = vec![
+            Token::new(Kind::Symbol, "("),
+            value.clone(),
+            Token::new(Kind::Symbol, ")"),
+        ];
+
+        edits.replace(source, p, p + 1, wrapped);
+    } else {
+        edits.replace(source, p, p + 1, vec![value.clone()]);
+    }
+}
@@ -0,0 +1,227 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Whitespace,
+    Comment,
+    Name,
+    Number,
+    String,
+    Symbol,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: Kind,
+    pub text: String,
+}
+
+impl Token {
+    pub fn new(kind: Kind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, Kind::Whitespace | Kind::Comment)
+    }
+
+    pub fn is(&self, text: &str) -> bool {
+        !self.is_trivia() && self.kind != Kind::String && self.text == text
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        self.kind == Kind::Name && KEYWORDS.contains(&self.text.as_str())
+    }
+
+    pub fn is_name(&self) -> bool {
+        self.kind == Kind::Name && !self.is_keyword()
+    }
+}
+
+pub const KEYWORDS: &[&str] = &[
+    "and", "break", "continue", "do", "else", "elseif", "end", "false", "for", "function", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+const SYMBOLS: &[&str] = &[
+    "...", "..=", "//=", "..", "==", "~=", "<=", ">=", "->", "::", "+=", "-=", "*=", "/=", "//",
+    "%=", "^=",
+];
+
+struct Lexer<'src> {
+    src: &'src str,
+    pos: usize,
+}
+
+impl<'src> Lexer<'src> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn rest(&self) -> &'src str {
+        &self.src[self.pos..]
+    }
+
+    fn long_bracket_level(&self) -> Option<usize> {
+        let rest = self.rest().as_bytes();
+        let level = rest.iter().skip(1).take_while(|&&c| c == b'=').count();
+
+        (rest.first() == Some(&b'[') && rest.get(level + 1) == Some(&b'[')).then_some(level)
+    }
+
+    fn skip_long_bracket(&mut self, level: usize) -> Result<(), String> {
+        let close = format!("]{}]", "=".repeat(level));
+
+        match self.src[self.pos + level + 2..].find(&close) {
+            Some(i) => {
+                self.pos += level + 2 + i + close.len();
+                Ok(())
+            }
+
+            None => Err("unfinished long string or comment".into()),
+        }
+    }
+
+    fn skip_quoted(&mut self, quote: u8) -> Result<(), String> {
+        self.pos += 1;
+
+        loop {
+            match self.peek(0) {
+                None | Some(b'\n') => return Err("unfinished string".into()),
+                Some(b'\\') => self.pos += 2,
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_interpolated(&mut self) -> Result<(), String> {
+        self.pos += 1;
+
+        loop {
+            match self.peek(0) {
+                None => return Err("unfinished interpolated string".into()),
+                Some(b'\\') => self.pos += 2,
+                Some(b'`') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+
+                Some(b'{') => {
+                    self.pos += 1;
+
+                    let mut depth = 1;
+                    while depth > 0 {
+                        let token = self.next().ok_or("unfinished interpolated string")??;
+
+                        if token.is("{") {
+                            depth += 1;
+                        } else if token.is("}") {
+                            depth -= 1;
+                        }
+                    }
+                }
+
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<Result<Token, String>> {
+        let start = self.pos;
+        let c = self.peek(0)?;
+
+        let kind = match c {
+            b' ' | b'\t' | b'\r' | b'\n' | b'\x0b' | b'\x0c' => {
+                while matches!(
+                    self.peek(0),
+                    Some(b' ' | b'\t' | b'\r' | b'\n' | b'\x0b' | b'\x0c')
+                ) {
+                    self.pos += 1;
+                }
+
+                Ok(Kind::Whitespace)
+            }
+
+            b'-' if self.peek(1) == Some(b'-') => {
+                self.pos += 2;
+
+                match self.long_bracket_level() {
+                    Some(level) => self.skip_long_bracket(level).map(|_| Kind::Comment),
+                    None => {
+                        self.pos += self.rest().find('\n').unwrap_or(self.rest().len());
+                        Ok(Kind::Comment)
+                    }
+                }
+            }
+
+            b'"' | b'\'' => self.skip_quoted(c).map(|_| Kind::String),
+            b'`' => self.skip_interpolated().map(|_| Kind::String),
+            b'[' if self.long_bracket_level().is_some() => {
+                let level = self.long_bracket_level().unwrap();
+                self.skip_long_bracket(level).map(|_| Kind::String)
+            }
+
+            b'0'..=b'9' => Ok(self.number()),
+            b'.' if matches!(self.peek(1), Some(b'0'..=b'9')) => Ok(self.number()),
+
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                while matches!(
+                    self.peek(0),
+                    Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_')
+                ) {
+                    self.pos += 1;
+                }
+
+                Ok(Kind::Name)
+            }
+
+            _ => {
+                let len = SYMBOLS
+                    .iter()
+                    .find(|s| self.rest().starts_with(*s))
+                    .map(|s| s.len())
+                    .unwrap_or_else(|| self.rest().chars().next().unwrap().len_utf8());
+
+                self.pos += len;
+                Ok(Kind::Symbol)
+            }
+        };
+
+        Some(kind.map(|kind| Token::new(kind, &self.src[start..self.pos.min(self.src.len())])))
+    }
+
+    fn number(&mut self) -> Kind {
+        let hex = self.rest().starts_with("0x") || self.rest().starts_with("0X");
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'e' | b'E' if !hex && matches!(self.peek(1), Some(b'+' | b'-')) => self.pos += 2,
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'.' => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        Kind::Number
+    }
+}
+
+pub fn lex(src: &str) -> Result<Vec<Token>, String> {
+    let mut lexer = Lexer { src, pos: 0 };
+    let mut tokens = Vec::new();
+
+    while let Some(token) = lexer.next() {
+        tokens.push(token?);
+    }
+
+    Ok(tokens)
+}
+
+pub fn render(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| t.text.as_str()).collect()
+}
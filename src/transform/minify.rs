@@ -0,0 +1,38 @@
+use super::lexer::{Kind, Token, lex};
+
+fn needs_space(a: &Token, b: &Token) -> bool {
+    match lex(&format!("{}{}", a.text, b.text)).as_deref() {
+        Ok([first, second]) => first.text != a.text || second.text != b.text,
+        _ => true,
+    }
+}
+
+pub fn minify(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut leading = true;
+
+    for token in tokens {
+        match token.kind {
+            Kind::Whitespace => continue,
+            Kind::Comment if leading && token.text.starts_with("--!") => {
+                out.push(token);
+                out.push(Token::new(Kind::Whitespace, "\n"));
+                continue;
+            }
+
+            Kind::Comment => continue,
+            _ => leading = false,
+        }
+
+        if out
+            .last()
+            .is_some_and(|prev| !prev.is_trivia() && needs_space(prev, &token))
+        {
+            out.push(Token::new(Kind::Whitespace, " "));
+        }
+
+        out.push(token);
+    }
+
+    out
+}
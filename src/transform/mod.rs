@@ -0,0 +1,271 @@
+use std::{collections::HashMap, path::Path};
+
+mod aliases;
+mod defines;
+mod lexer;
+mod minify;
+mod strip;
+mod syntax;
+mod types;
+
+#[derive(Default, Clone)]
+pub struct Transform {
+    minify: bool,
+    strip: Vec<String>,
+    strip_types: bool,
+    defines: HashMap<String, lexer::Token>,
+    aliases: bool,
+}
+
+impl Transform {
+    pub fn with_minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    pub fn with_strip(mut self, names: Vec<String>) -> Self {
+        self.strip = names;
+        self
+    }
+
+    pub fn with_strip_types(mut self, strip_types: bool) -> Self {
+        self.strip_types = strip_types;
+        self
+    }
+
+    pub fn with_define(mut self, define: &str) -> Result<Self, String> {
+        let (name, value) = defines::parse_define(define)?;
+        self.defines.insert(name, value);
+        Ok(self)
+    }
+
+    pub fn with_aliases(mut self, aliases: bool) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub fn apply(&self, path: &Path, source: &str) -> Result<String, String> {
+        let mut tokens = lexer::lex(source).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        if !self.defines.is_empty() {
+            tokens = defines::inline_defines(tokens, &self.defines);
+        }
+
+        if !self.strip.is_empty() || !self.defines.is_empty() {
+            tokens = strip::strip_debug(tokens, &self.strip);
+        }
+
+        if self.strip_types {
+            tokens = types::strip_types(tokens);
+        }
+
+        if self.aliases {
+            tokens = aliases::rewrite_requires(tokens, path)?;
+        }
+
+        if self.minify {
+            tokens = minify::minify(tokens);
+        }
+
+        Ok(lexer::render(&tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: Transform, source: &str) -> String {
+        transform.apply(Path::new("test.luau"), source).unwrap()
+    }
+
+    fn define(source: &str) -> String {
+        let transform = Transform::default()
+            .with_define("DEBUG")
+            .and_then(|t| t.with_define("VERSION=1.2"))
+            .unwrap();
+
+        apply(transform, source)
+    }
+
+    #[test]
+    fn minify() {
+        let minify = |source| apply(Transform::default().with_minify(true), source);
+
+        assert_eq!(
+            minify("--!strict\n-- comment\nlocal  x = 1 -- trailing\nreturn x  +  1\n"),
+            "--!strict\nlocal x=1 return x+1"
+        );
+        assert_eq!(minify("local a = b - -c"), "local a=b- -c");
+        assert_eq!(minify("print(1 .. 2, a .. b)"), "print(1 ..2,a..b)");
+        assert_eq!(
+            minify("local s = [[ keep  this ]]"),
+            "local s=[[ keep  this ]]"
+        );
+        assert_eq!(minify("print(`{a}  {b}`)"), "print(`{a}  {b}`)");
+        assert_eq!(minify("x = 0x10 y = 1e-3"), "x=0x10 y=1e-3");
+    }
+
+    #[test]
+    fn strip() {
+        let strip = |source| {
+            apply(
+                Transform::default().with_strip(vec!["DEBUG".into()]),
+                source,
+            )
+        };
+
+        assert_eq!(
+            strip("print(1)\nif DEBUG then\n  print(2)\nend\nprint(3)\n"),
+            "print(1)\n\nprint(3)\n"
+        );
+        assert_eq!(
+            strip("if DEBUG then print(1) else print(2) end"),
+            "do print(2) end"
+        );
+        assert_eq!(
+            strip("if true then print(1) else print(2) end"),
+            "do print(1) end"
+        );
+        assert_eq!(
+            strip("if DEBUG then if x then print(1) end end print(2)"),
+            " print(2)"
+        );
+
+        // left alone
+        for source in [
+            "local x = if DEBUG then 1 else 2",
+            "if DEBUG then print(1) elseif x then print(2) end",
+            "if DEBUG and x then print(1) end",
+            "if OTHER then print(1) end",
+        ] {
+            assert_eq!(strip(source), source);
+        }
+    }
+
+    #[test]
+    fn defines() {
+        assert_eq!(
+            define("if DEBUG then print(VERSION) end"),
+            "do print(1.2) end"
+        );
+        assert_eq!(
+            define("print(DEBUG, t.DEBUG, t:DEBUG(), x :: DEBUG)"),
+            "print(true, t.DEBUG, t:DEBUG(), x :: DEBUG)"
+        );
+        assert_eq!(
+            define("local t = { DEBUG = DEBUG, [DEBUG] = 1 }"),
+            "local t = { DEBUG = true, [true] = 1 }"
+        );
+        assert_eq!(
+            define("x = if DEBUG then 1 else 2"),
+            "x = if true then 1 else 2"
+        );
+        assert_eq!(define("VERSION:upper()"), "(1.2):upper()");
+        assert_eq!(define("DEBUG.x = 1"), "(true).x = 1");
+        assert_eq!(define("return DEBUG, VERSION"), "return true, 1.2");
+        assert_eq!(
+            define("local a, b = DEBUG, x\ny = 2"),
+            "local a, b = true, x\ny = 2"
+        );
+    }
+
+    #[test]
+    fn defines_leave_bindings() {
+        // assignment targets
+        for source in [
+            "DEBUG = 1",
+            "DEBUG += 1",
+            "t.x, DEBUG = 1, 2",
+            "DEBUG, t[1] = 1, 2",
+            "function DEBUG() end",
+            "f(function() DEBUG = 1 end)",
+        ] {
+            assert_eq!(define(source), source);
+        }
+
+        // locals and parameters shadow the define in their scope
+        assert_eq!(
+            define("local a, DEBUG = 1, 2\nprint(DEBUG)"),
+            "local a, DEBUG = 1, 2\nprint(DEBUG)"
+        );
+        assert_eq!(
+            define("do local DEBUG = false print(DEBUG) end print(DEBUG)"),
+            "do local DEBUG = false print(DEBUG) end print(true)"
+        );
+        assert_eq!(
+            define("local function f(DEBUG: boolean) return DEBUG end print(DEBUG)"),
+            "local function f(DEBUG: boolean) return DEBUG end print(true)"
+        );
+        assert_eq!(
+            define("for _, DEBUG in t do print(DEBUG) end print(DEBUG)"),
+            "for _, DEBUG in t do print(DEBUG) end print(true)"
+        );
+        assert_eq!(
+            define("if x then local DEBUG = 1 else print(DEBUG) end"),
+            "if x then local DEBUG = 1 else print(true) end"
+        );
+        assert_eq!(
+            define("local function DEBUG() end DEBUG()"),
+            "local function DEBUG() end DEBUG()"
+        );
+    }
+
+    #[test]
+    fn defines_leave_types() {
+        for source in [
+            "type T = DEBUG",
+            "export type T<A = DEBUG> = { [DEBUG]: A | DEBUG? }",
+            "type F = (DEBUG) -> (DEBUG, Map<DEBUG, DEBUG>)",
+            "local x: { DEBUG } | DEBUG = y",
+            "local f: (DEBUG) -> DEBUG = g",
+            "local y = x :: Map<string, DEBUG>",
+            "function f<T>(a: Map<T, DEBUG>): (DEBUG, DEBUG) end",
+        ] {
+            assert_eq!(define(source), source);
+        }
+
+        // only the types are left alone
+        assert_eq!(
+            define("type T = DEBUG\nlocal x: DEBUG = DEBUG :: DEBUG"),
+            "type T = DEBUG\nlocal x: DEBUG = true :: DEBUG"
+        );
+        assert_eq!(
+            define("local function f(): DEBUG return DEBUG end"),
+            "local function f(): DEBUG return true end"
+        );
+        assert_eq!(define("print(type(DEBUG))"), "print(type(true))");
+    }
+
+    #[test]
+    fn strip_types() {
+        let strip = |source| apply(Transform::default().with_strip_types(true), source);
+
+        assert_eq!(
+            strip("local x: number = 1\nreturn x :: any"),
+            "local x = 1\nreturn x"
+        );
+        assert_eq!(
+            strip(
+                "export type T<A> = { a: A }\nlocal function f<T>(a: T, ...: string): (T, number) return a, 1 end"
+            ),
+            "\nlocal function f(a, ...) return a, 1 end"
+        );
+        assert_eq!(
+            strip("for i: number, v: string in t do end"),
+            "for i, v in t do end"
+        );
+    }
+
+    #[test]
+    fn parse_define() {
+        let value = |define| defines::parse_define(define).map(|(_, token)| token.text);
+
+        assert_eq!(value("A"), Ok("true".into()));
+        assert_eq!(value("A=12"), Ok("12".into()));
+        assert_eq!(value("A='x'"), Ok("'x'".into()));
+        assert_eq!(value("A=hello world"), Ok("\"hello world\"".into()));
+        assert!(value("local=1").is_err());
+        assert!(value("a.b").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use super::{
+    lexer::{Kind, Token},
+    syntax::{Edits, Source},
+};
+
+fn condition(token: &Token, names: &[String]) -> Option<bool> {
+    if token.is("true") {
+        Some(true)
+    } else if token.is("false")
+        || token.is("nil")
+        || (token.is_name() && names.contains(&token.text))
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+pub fn strip_debug(tokens: Vec<Token>, names: &[String]) -> Vec<Token> {
+    let source = Source::new(tokens);
+    let mut edits = Edits::new();
+    let mut p = 0;
+
+    while p < source.len() {
+        if !source.is_statement_if(p) || !source.is(p + 2, "then") {
+            p += 1;
+            continue;
+        }
+
+        let Some(taken) = condition(source.get(p + 1).unwrap(), names) else {
+            p += 1;
+            continue;
+        };
+
+        let Some((end, branches)) = source.find_end(p) else {
+            p += 1;
+            continue;
+        };
+
+        if branches.len() > 1 || branches.iter().any(|&b| source.is(b, "elseif")) {
+            p += 1;
+            continue;
+        }
+
+        let keep = || vec![Token::new(Kind::Name, "do")];
+
+        p = match (taken, branches.first().copied()) {
+            (false, None) => {
+                edits.remove(&source, p, end + 1);
+                end + 1
+            }
+
+            (false, Some(other)) => {
+                edits.replace(&source, p, other + 1, keep());
+                other + 1
+            }
+
+            (true, None) => {
+                edits.replace(&source, p, p + 3, keep());
+                p + 3
+            }
+
+            (true, Some(other)) => {
+                edits.replace(&source, p, p + 3, keep());
+                edits.remove(&source, other, end);
+                p + 3
+            }
+        };
+    }
+
+    edits.apply(source.tokens)
+}
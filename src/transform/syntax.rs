@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+
+use super::lexer::{Kind, Token};
+
+pub struct Source {
+    pub tokens: Vec<Token>,
+    sig: Vec<usize>,
+    expr_ifs: HashSet<usize>,
+}
+
+enum Frame {
+    Block,
+    Repeat,
+    ExprIf,
+}
+
+impl Source {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let sig = (0..tokens.len())
+            .filter(|&i| !tokens[i].is_trivia())
+            .collect();
+        let mut source = Self {
+            tokens,
+            sig,
+            expr_ifs: HashSet::new(),
+        };
+
+        source.expr_ifs = source.find_expression_ifs();
+        source
+    }
+
+    pub fn len(&self) -> usize {
+        self.sig.len()
+    }
+
+    pub fn get(&self, p: usize) -> Option<&Token> {
+        self.sig.get(p).map(|&i| &self.tokens[i])
+    }
+
+    pub fn is(&self, p: usize, text: &str) -> bool {
+        self.get(p).is_some_and(|t| t.is(text))
+    }
+
+    pub fn is_name(&self, p: usize) -> bool {
+        self.get(p).is_some_and(|t| t.is_name())
+    }
+
+    pub fn index(&self, p: usize) -> usize {
+        self.sig.get(p).copied().unwrap_or(self.tokens.len())
+    }
+
+    fn prev(&self, p: usize) -> Option<&Token> {
+        p.checked_sub(1).and_then(|p| self.get(p))
+    }
+
+    /// Whether the token before `p` leaves the parser expecting an expression.
+    pub fn expects_expression(&self, p: usize) -> bool {
+        match self.prev(p) {
+            None => false,
+            Some(t) if t.kind == Kind::Symbol => !matches!(t.text.as_str(), ")" | "]" | "}" | ";"),
+            Some(t) => matches!(
+                t.text.as_str(),
+                "return" | "and" | "or" | "not" | "in" | "until" | "while" | "if" | "elseif"
+            ),
+        }
+    }
+
+    pub fn is_statement_if(&self, p: usize) -> bool {
+        self.is(p, "if") && !self.expr_ifs.contains(&p)
+    }
+
+    fn find_expression_ifs(&self) -> HashSet<usize> {
+        let mut stack = Vec::new();
+        let mut ifs = HashSet::new();
+
+        for p in 0..self.len() {
+            let token = self.get(p).unwrap();
+            if token.kind != Kind::Name {
+                continue;
+            }
+
+            let in_expr_if = matches!(stack.last(), Some(Frame::ExprIf));
+
+            match token.text.as_str() {
+                "if" => {
+                    let after_branch =
+                        in_expr_if && self.prev(p).is_some_and(|t| t.is("then") || t.is("else"));
+
+                    if self.expects_expression(p) || after_branch {
+                        ifs.insert(p);
+                        stack.push(Frame::ExprIf);
+                    } else {
+                        stack.push(Frame::Block);
+                    }
+                }
+
+                "else" if in_expr_if => {
+                    stack.pop();
+                }
+
+                "function" | "do" => stack.push(Frame::Block),
+                "repeat" => stack.push(Frame::Repeat),
+                "end" | "until" => {
+                    while let Some(Frame::ExprIf) = stack.last() {
+                        stack.pop();
+                    }
+
+                    stack.pop();
+                }
+
+                _ => {}
+            }
+        }
+
+        ifs
+    }
+
+    /// Finds the `end` or `until` closing the block opened at `p`, returning
+    /// the positions of any `elseif`/`else` belonging to it along the way.
+    pub fn find_end(&self, p: usize) -> Option<(usize, Vec<usize>)> {
+        let mut depth = 0usize;
+        let mut branches = Vec::new();
+
+        for q in p..self.len() {
+            let token = self.get(q).unwrap();
+            if token.kind != Kind::Name {
+                continue;
+            }
+
+            match token.text.as_str() {
+                "if" if self.is_statement_if(q) => depth += 1,
+                "function" | "do" | "repeat" => depth += 1,
+                "else" | "elseif" if depth == 1 && !self.in_expression_if(p, q) => branches.push(q),
+                "end" | "until" => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Some((q, branches));
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn in_expression_if(&self, from: usize, to: usize) -> bool {
+        let mut open = 0usize;
+
+        for q in from..to {
+            if self.expr_ifs.contains(&q) {
+                open += 1;
+            } else if open > 0 && self.is(q, "else") {
+                open -= 1;
+            }
+        }
+
+        open > 0
+    }
+
+    /// Skips over a bracketed group starting at `p`, returning the position
+    /// just after its closing bracket.
+    pub fn skip_balanced(&self, p: usize) -> usize {
+        let mut depth = 0usize;
+
+        for q in p..self.len() {
+            let token = self.get(q).unwrap();
+            if token.kind != Kind::Symbol {
+                continue;
+            }
+
+            match token.text.as_str() {
+                "(" | "{" | "[" | "<" => depth += 1,
+                ")" | "}" | "]" | ">" => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return q + 1;
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        self.len()
+    }
+
+    /// Skips over a type annotation starting at `p`, returning the position
+    /// just after it.
+    pub fn skip_type(&self, mut p: usize) -> usize {
+        if self.is(p, "|") || self.is(p, "&") {
+            p += 1;
+        }
+
+        loop {
+            p = self.skip_simple_type(p);
+
+            while self.is(p, "?") {
+                p += 1;
+            }
+
+            if self.is(p, "|") || self.is(p, "&") {
+                p += 1;
+            } else {
+                return p;
+            }
+        }
+    }
+
+    fn skip_simple_type(&self, p: usize) -> usize {
+        let Some(token) = self.get(p) else {
+            return p;
+        };
+
+        match (token.kind, token.text.as_str()) {
+            (Kind::Symbol, "(") => {
+                let p = self.skip_balanced(p);
+
+                if self.is(p, "->") {
+                    self.skip_type(p + 1)
+                } else {
+                    p
+                }
+            }
+
+            (Kind::Symbol, "<") => self.skip_simple_type(self.skip_balanced(p)),
+            (Kind::Symbol, "{") => self.skip_balanced(p),
+            (Kind::Symbol, "...") => self.skip_type(p + 1),
+            (Kind::Name, "typeof") if self.is(p + 1, "(") => self.skip_balanced(p + 1),
+            (Kind::String, _) => p + 1,
+            (Kind::Name, _) => {
+                let mut p = p + 1;
+
+                while self.is(p, ".") && self.is_name(p + 1) {
+                    p += 2;
+                }
+
+                if self.is(p, "<") {
+                    p = self.skip_balanced(p);
+                }
+
+                if self.is(p, "...") {
+                    p += 1;
+                }
+
+                p
+            }
+
+            _ => p,
+        }
+    }
+}
+
+pub struct Edits(Vec<(usize, usize, Vec<Token>)>);
+
+impl Edits {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Replaces the significant tokens in `from..to` and any trivia between
+    /// them.
+    pub fn replace(&mut self, source: &Source, from: usize, to: usize, with: Vec<Token>) {
+        let start = source.index(from);
+        let end = source.index(to - 1) + 1;
+
+        self.0.push((start, end, with));
+    }
+
+    /// Removes the significant tokens in `from..to`, along with any
+    /// whitespace leading up to them on the same line.
+    pub fn remove(&mut self, source: &Source, from: usize, to: usize) {
+        let mut start = source.index(from);
+        let end = source.index(to - 1) + 1;
+
+        if let Some(prev) = start.checked_sub(1).map(|i| &source.tokens[i])
+            && prev.kind == Kind::Whitespace
+            && !prev.text.contains('\n')
+        {
+            start -= 1;
+        }
+
+        self.0.push((start, end, Vec::new()));
+    }
+
+    pub fn apply(mut self, tokens: Vec<Token>) -> Vec<Token> {
+        self.0.sort_by_key(|&(start, end, _)| (start, end));
+
+        let mut out = Vec::with_capacity(tokens.len());
+        let mut tokens = tokens.into_iter();
+        let mut cursor = 0;
+
+        for (start, end, with) in self.0 {
+            if start < cursor {
+                continue;
+            }
+
+            out.extend(tokens.by_ref().take(start - cursor));
+            tokens.by_ref().take(end - start).for_each(drop);
+            out.extend(with);
+
+            cursor = end;
+        }
+
+        out.extend(tokens);
+        out
+    }
+}
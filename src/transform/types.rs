@@ -0,0 +1,127 @@
+use super::{
+    lexer::{Kind, Token},
+    syntax::{Edits, Source},
+};
+
+fn annotated_names(source: &Source, edits: &mut Edits, mut p: usize) -> usize {
+    while source.is_name(p) || source.is(p, "...") {
+        p += 1;
+
+        if source.is(p, ":") {
+            let end = source.skip_type(p + 1);
+            edits.remove(source, p, end);
+            p = end;
+        }
+
+        if source.is(p, ",") {
+            p += 1;
+        } else {
+            break;
+        }
+    }
+
+    p
+}
+
+fn function_signature(source: &Source, edits: &mut Edits, mut p: usize) -> usize {
+    if source.is_name(p) {
+        p += 1;
+
+        while (source.is(p, ".") || source.is(p, ":")) && source.is_name(p + 1) {
+            p += 2;
+        }
+    }
+
+    if source.is(p, "<") {
+        let end = source.skip_balanced(p);
+        edits.remove(source, p, end);
+        p = end;
+    }
+
+    if !source.is(p, "(") {
+        return p;
+    }
+
+    p = annotated_names(source, edits, p + 1);
+
+    if source.is(p, ")") {
+        p += 1;
+
+        if source.is(p, ":") {
+            let end = source.skip_type(p + 1);
+            edits.remove(source, p, end);
+            p = end;
+        }
+    }
+
+    p
+}
+
+pub fn is_type_statement(source: &Source, p: usize) -> bool {
+    source
+        .get(p)
+        .is_some_and(|t| t.kind == Kind::Name && t.text == "type")
+        && (source.is_name(p + 1) || source.is(p + 1, "function"))
+        && !source.expects_expression(p)
+}
+
+pub fn type_statement_end(source: &Source, p: usize) -> usize {
+    if source.is(p + 1, "function") {
+        return source
+            .find_end(p + 1)
+            .map(|(end, _)| end + 1)
+            .unwrap_or(source.len());
+    }
+
+    let mut p = p + 2;
+
+    if source.is(p, "<") {
+        p = source.skip_balanced(p);
+    }
+
+    if source.is(p, "=") {
+        p = source.skip_type(p + 1);
+    }
+
+    p
+}
+
+pub fn strip_types(tokens: Vec<Token>) -> Vec<Token> {
+    let source = Source::new(tokens);
+    let mut edits = Edits::new();
+    let mut p = 0;
+
+    while let Some(token) = source.get(p) {
+        if token.kind == Kind::String {
+            p += 1;
+            continue;
+        }
+
+        p = match token.text.as_str() {
+            "local" if !source.is(p + 1, "function") => annotated_names(&source, &mut edits, p + 1),
+            "for" => annotated_names(&source, &mut edits, p + 1),
+            "function" => function_signature(&source, &mut edits, p + 1),
+            "::" => {
+                let end = source.skip_type(p + 1);
+                edits.remove(&source, p, end);
+                end
+            }
+
+            "export" if is_type_statement(&source, p + 1) => {
+                let end = type_statement_end(&source, p + 1);
+                edits.remove(&source, p, end);
+                end
+            }
+
+            "type" if is_type_statement(&source, p) => {
+                let end = type_statement_end(&source, p);
+                edits.remove(&source, p, end);
+                end
+            }
+
+            _ => p + 1,
+        };
+    }
+
+    edits.apply(source.tokens)
+}
@@ -0,0 +1,91 @@
+//! Runs `bre run` on small projects to check how failing requires surface.
+
+use std::{fs, process::Command};
+
+fn run(files: &[(&str, &str)]) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+
+    for (name, source) in files {
+        fs::write(dir.path().join(name), source).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bre"))
+        .arg("run")
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn pcall_sees_the_require_chain() {
+    let (stdout, _) = run(&[
+        ("main.luau", "print(pcall(require, './b'))\n"),
+        ("b.luau", "return require('./c')\n"),
+        ("c.luau", "error('boom')\n"),
+    ]);
+
+    let (ok, err) = stdout.trim_end().split_once('\t').unwrap();
+    assert_eq!(ok, "false");
+
+    // chunknames are the modules' paths
+    assert!(err.starts_with("while requiring '"), "{err}");
+    assert!(err.contains("b.luau': while requiring '"), "{err}");
+    assert!(err.contains("c.luau': "), "{err}");
+    assert!(err.ends_with("boom"), "{err}");
+}
+
+#[test]
+fn pcall_sees_load_failures() {
+    let (stdout, _) = run(&[
+        ("main.luau", "print(pcall(require, './b'))\n"),
+        ("b.luau", "return require('./c')\n"),
+        ("c.luau", "local = 1\n"),
+    ]);
+
+    assert!(stdout.starts_with("false\twhile requiring '"), "{stdout}");
+    assert!(stdout.contains("b.luau': while requiring '"), "{stdout}");
+    assert!(stdout.contains("c.luau': "), "{stdout}");
+}
+
+#[test]
+fn uncaught_errors_report_the_chain() {
+    let (_, stderr) = run(&[
+        ("main.luau", "require('./b')\n"),
+        ("b.luau", "return require('./c')\n"),
+        ("c.luau", "error('boom')\n"),
+    ]);
+
+    assert!(stderr.contains("boom"), "{stderr}");
+    assert!(stderr.contains("require chain:"), "{stderr}");
+    assert!(stderr.contains("b.luau"), "{stderr}");
+}
+
+#[test]
+fn binary_extensions_load_as_buffers() {
+    let (stdout, _) = run(&[
+        ("main.luau", "print(typeof(require('./data.bin')))\n"),
+        ("data.bin", "\x00\x01"),
+    ]);
+
+    assert_eq!(stdout, "buffer\n");
+}
+
+#[test]
+fn unknown_extensions_are_not_found() {
+    let (stdout, _) = run(&[
+        ("main.luau", "print(pcall(require, './notes.xyz'))\n"),
+        ("notes.xyz", "hello"),
+    ]);
+
+    assert!(stdout.starts_with("false\t"), "{stdout}");
+    assert!(
+        stdout.contains("'.xyz' files can't be required"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("notes.xyz.luau"), "{stdout}");
+}